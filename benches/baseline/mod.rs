@@ -0,0 +1,377 @@
+//! The str-based generators from before parsing moved to bytes (92b3beb), along with the
+//! helpers they used, kept so the benchmarks can compare against them. They build the
+//! same structures the solvers used at the time, which are only timed and never read.
+#![allow(dead_code)]
+
+use aoc_2024::ParseError;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take};
+use nom::character::complete::{char, digit1};
+use nom::combinator::{map, map_res};
+use nom::multi::{many0, many_till};
+use nom::sequence::{delimited, separated_pair};
+use nom::IResult;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+fn trim(input: &str) -> &str {
+    input.trim_start_matches(BYTE_ORDER_MARK).trim()
+}
+
+fn lines(input: &str) -> impl Iterator<Item = &str> + Clone {
+    trim(input).lines().map(str::trim)
+}
+
+struct Section<'input> {
+    first_line: usize,
+    text: &'input str,
+}
+
+fn sections(input: &str) -> Vec<Section<'_>> {
+    let input = trim(input);
+    let mut sections = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    for (index, line) in input.split_inclusive('\n').enumerate() {
+        match (line.trim().is_empty(), start) {
+            (false, None) => start = Some((index + 1, offset)),
+            (true, Some((first_line, start_offset))) => {
+                sections.push(Section {
+                    first_line,
+                    text: &input[start_offset..offset],
+                });
+                start = None;
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+    if let Some((first_line, start_offset)) = start {
+        sections.push(Section {
+            first_line,
+            text: &input[start_offset..],
+        });
+    }
+    sections
+}
+
+fn parse_number<T: FromStr>(text: &str, line: usize) -> Result<T, ParseError> {
+    text.parse().map_err(|_| ParseError::InvalidNumber {
+        line,
+        text: text.to_owned(),
+    })
+}
+
+fn parse_location_ids(line: &str, line_number: usize) -> Result<(u32, u32), ParseError> {
+    let mut line = line.split_whitespace();
+    match (line.next(), line.next()) {
+        (Some(first), Some(second)) => Ok((
+            parse_number(first, line_number)?,
+            parse_number(second, line_number)?,
+        )),
+        _ => Err(ParseError::Malformed {
+            line: line_number,
+            expected: "two location IDs",
+        }),
+    }
+}
+
+// Day 1 parsed inside its parts, so this is the part of them that did the parsing.
+pub fn day1(input: &str) -> Result<(Vec<u32>, Vec<u32>), ParseError> {
+    let mut lines = lines(input)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty());
+    let count = lines.clone().count();
+    lines.try_fold(
+        (Vec::with_capacity(count), Vec::with_capacity(count)),
+        |(mut left, mut right), (index, line)| {
+            let (first, second) = parse_location_ids(line, index + 1)?;
+            left.push(first);
+            right.push(second);
+            Ok((left, right))
+        },
+    )
+}
+
+pub fn day2(input: &str) -> Result<Vec<Vec<i8>>, ParseError> {
+    lines(input)
+        .enumerate()
+        .map(|(index, line)| {
+            let report = line
+                .split_whitespace()
+                .map(|num| parse_number(num, index + 1))
+                .collect::<Result<Vec<_>, _>>()?;
+            if report.is_empty() {
+                Err(ParseError::Malformed {
+                    line: index + 1,
+                    expected: "at least one level",
+                })
+            } else {
+                Ok(report)
+            }
+        })
+        .collect()
+}
+
+pub enum Command {
+    Mul(u32, u32),
+    Enable,
+    Disable,
+}
+
+fn parse_integer(input: &str) -> IResult<&str, u32> {
+    map_res(digit1, |digit_str: &str| digit_str.parse())(input)
+}
+
+fn parse_mul(input: &str) -> IResult<&str, Command> {
+    let (input, (left, right)) = delimited(
+        tag("mul("),
+        separated_pair(parse_integer, char(','), parse_integer),
+        char(')'),
+    )(input)?;
+    Ok((input, Command::Mul(left, right)))
+}
+
+fn parse_enable(input: &str) -> IResult<&str, Command> {
+    let (input, _) = tag("do()")(input)?;
+    Ok((input, Command::Enable))
+}
+
+fn parse_disable(input: &str) -> IResult<&str, Command> {
+    let (input, _) = tag("don't()")(input)?;
+    Ok((input, Command::Disable))
+}
+
+fn parse_command(input: &str) -> IResult<&str, Command> {
+    alt((parse_mul, parse_enable, parse_disable))(input)
+}
+
+fn parse_command_with_prefix(input: &str) -> IResult<&str, Command> {
+    map(many_till(take(1u8), parse_command), |(_, matched)| matched)(input)
+}
+
+fn parse_all_commands(input: &str) -> IResult<&str, Vec<Command>> {
+    many0(parse_command_with_prefix)(input)
+}
+
+pub fn day3(input: &str) -> Vec<Command> {
+    let (_, results) = parse_all_commands(trim(input)).unwrap();
+    results
+}
+
+const MAX_GRID_LENGTH: usize = 19_600;
+
+pub struct WordSearch {
+    storage: [char; MAX_GRID_LENGTH],
+    number_of_columns: usize,
+    number_of_rows: usize,
+}
+
+pub fn day4(input: &str) -> Result<WordSearch, ParseError> {
+    let mut storage = ['\0'; MAX_GRID_LENGTH];
+    let mut index = 0;
+    let mut number_of_columns = 0;
+    let mut number_of_rows = 1;
+    let mut lines = lines(input);
+    let too_large = ParseError::TooLarge {
+        limit: MAX_GRID_LENGTH,
+    };
+    for character in lines.next().ok_or(ParseError::Empty)?.chars() {
+        number_of_columns += 1;
+        *storage.get_mut(index).ok_or(too_large.clone())? = character;
+        index += 1;
+    }
+    for line in lines {
+        number_of_rows += 1;
+        for character in line.chars() {
+            *storage.get_mut(index).ok_or(too_large.clone())? = character;
+            index += 1;
+        }
+    }
+    if number_of_rows * number_of_columns > MAX_GRID_LENGTH {
+        return Err(too_large);
+    }
+    Ok(WordSearch {
+        storage,
+        number_of_columns,
+        number_of_rows,
+    })
+}
+
+type Rules<'input> = HashMap<(&'input str, &'input str), bool>;
+
+fn parse_rules(input: &str) -> Result<Rules<'_>, ParseError> {
+    let mut rules = HashMap::new();
+    for (index, line) in lines(input).enumerate() {
+        let (first, second) = line.split_once('|').ok_or(ParseError::Malformed {
+            line: index + 1,
+            expected: "a rule like 47|53",
+        })?;
+        rules.insert((first, second), true);
+        rules.insert((second, first), false);
+    }
+    Ok(rules)
+}
+
+fn parse_updates(input: &str, first_line: usize) -> Result<Vec<Vec<&str>>, ParseError> {
+    let mut updates = Vec::new();
+    for (index, line) in lines(input).enumerate() {
+        let update: Vec<&str> = line.split(',').collect();
+        for page in &update {
+            parse_number::<u32>(page, first_line + index)?;
+        }
+        updates.push(update);
+    }
+    Ok(updates)
+}
+
+pub fn day5(input: &str) -> Result<(Rules<'_>, Vec<Vec<&str>>), ParseError> {
+    match sections(input)[..] {
+        [ref rules, ref updates] => Ok((
+            parse_rules(rules.text)?,
+            parse_updates(updates.text, updates.first_line)?,
+        )),
+        [_, _, ref extra, ..] => Err(ParseError::Malformed {
+            line: extra.first_line,
+            expected: "nothing after the updates",
+        }),
+        _ => Err(ParseError::Malformed {
+            line: lines(input).count(),
+            expected: "a blank line between the rules and the updates",
+        }),
+    }
+}
+
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+pub struct Map {
+    obstacles: [bool; MAX_GRID_LENGTH],
+    number_of_columns: usize,
+    number_of_rows: usize,
+    guard: ((usize, usize), Direction),
+}
+
+pub fn day6(input: &str) -> Result<Map, ParseError> {
+    let mut obstacles = [false; MAX_GRID_LENGTH];
+    let mut index = 0;
+    let mut number_of_columns = 0;
+    let mut number_of_rows = 1;
+    let mut lines = lines(input);
+    let mut guard = (0, Direction::Up);
+    let too_large = ParseError::TooLarge {
+        limit: MAX_GRID_LENGTH,
+    };
+    for character in lines.next().ok_or(ParseError::Empty)?.chars() {
+        if index == MAX_GRID_LENGTH {
+            return Err(too_large);
+        }
+        number_of_columns += 1;
+        obstacles[index] = character == '#';
+        match character {
+            '#' => obstacles[index] = true,
+            '>' => guard = (index, Direction::Right),
+            '<' => guard = (index, Direction::Left),
+            '^' => guard = (index, Direction::Up),
+            'v' => guard = (index, Direction::Down),
+            _ => {}
+        }
+        index += 1;
+    }
+    for line in lines {
+        number_of_rows += 1;
+        for character in line.chars() {
+            if index == MAX_GRID_LENGTH {
+                return Err(too_large);
+            }
+            match character {
+                '#' => obstacles[index] = true,
+                '>' => guard = (index, Direction::Right),
+                '<' => guard = (index, Direction::Left),
+                '^' => guard = (index, Direction::Up),
+                'v' => guard = (index, Direction::Down),
+                _ => {}
+            }
+            index += 1;
+        }
+    }
+    if number_of_rows * number_of_columns > MAX_GRID_LENGTH {
+        return Err(too_large);
+    }
+    let (guard_index, guard_direction) = guard;
+    let (row, column) = (
+        guard_index / number_of_columns,
+        guard_index % number_of_columns,
+    );
+    Ok(Map {
+        obstacles,
+        number_of_columns,
+        number_of_rows,
+        guard: ((row, column), guard_direction),
+    })
+}
+
+const MAX_NUMBER_OF_EQUATIONS: usize = 850;
+const MAX_TERMS_PER_EQUATION: usize = 12;
+
+#[derive(Clone, Copy)]
+struct Equation {
+    result: u64,
+    terms: [u64; MAX_TERMS_PER_EQUATION],
+    len: usize,
+}
+
+impl Default for Equation {
+    fn default() -> Self {
+        Self {
+            result: 0,
+            terms: [0; MAX_TERMS_PER_EQUATION],
+            len: 0,
+        }
+    }
+}
+
+pub struct Equations {
+    equations: [Equation; MAX_NUMBER_OF_EQUATIONS],
+    len: usize,
+}
+
+pub fn day7(input: &str) -> Result<Equations, ParseError> {
+    let mut equations_array = [Equation::default(); MAX_NUMBER_OF_EQUATIONS];
+    let mut equations_len = 0;
+    for (index, line) in lines(input).enumerate() {
+        let (result, terms) = line.split_once(": ").ok_or(ParseError::Malformed {
+            line: index + 1,
+            expected: "an equation like 190: 10 19",
+        })?;
+        let result = parse_number(result, index + 1)?;
+        let mut terms_array = [0; MAX_TERMS_PER_EQUATION];
+        let mut terms_len = 0;
+        for term in terms.split(' ') {
+            *terms_array.get_mut(terms_len).ok_or(ParseError::TooLarge {
+                limit: MAX_TERMS_PER_EQUATION,
+            })? = parse_number(term, index + 1)?;
+            terms_len += 1;
+        }
+        *equations_array
+            .get_mut(equations_len)
+            .ok_or(ParseError::TooLarge {
+                limit: MAX_NUMBER_OF_EQUATIONS,
+            })? = Equation {
+            result,
+            terms: terms_array,
+            len: terms_len,
+        };
+        equations_len += 1;
+    }
+    Ok(Equations {
+        equations: equations_array,
+        len: equations_len,
+    })
+}
@@ -0,0 +1,40 @@
+mod baseline;
+
+use aoc_2024::{day1, day2, day3, day4, day5, day6, day7};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// Each day's generator is timed next to the str-based one it replaced, so criterion's
+// report shows how much faster parsing from bytes is.
+fn compare<Before, After>(
+    c: &mut Criterion,
+    day: u32,
+    input: &'static str,
+    before: impl Fn(&'static str) -> Before,
+    after: impl Fn(&'static str) -> After,
+) {
+    let mut group = c.benchmark_group(format!("day{day} generator"));
+    group.bench_function("str", |b| b.iter(|| before(black_box(input))));
+    group.bench_function("bytes", |b| b.iter(|| after(black_box(input))));
+    group.finish();
+}
+
+fn parsing(c: &mut Criterion) {
+    let day1 = include_str!("../input/2024/day1.txt");
+    let day2 = include_str!("../input/2024/day2.txt");
+    let day3 = include_str!("../input/2024/day3.txt");
+    let day4 = include_str!("../input/2024/day4.txt");
+    let day5 = include_str!("../input/2024/day5.txt");
+    let day6 = include_str!("../input/2024/day6.txt");
+    let day7 = include_str!("../input/2024/day7.txt");
+
+    compare(c, 1, day1, baseline::day1, day1::parse_lists);
+    compare(c, 2, day2, baseline::day2, day2::input_generator);
+    compare(c, 3, day3, baseline::day3, day3::input_generator);
+    compare(c, 4, day4, baseline::day4, day4::input_generator);
+    compare(c, 5, day5, baseline::day5, day5::parse_input);
+    compare(c, 6, day6, baseline::day6, day6::input_generator);
+    compare(c, 7, day7, baseline::day7, day7::input_generator);
+}
+
+criterion_group!(benches, parsing);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+// With the `embedded-inputs` feature, writes a table of `include_str!`s for whichever
+// `input/2024/dayN.txt` files exist, so that days without one are simply left out.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_EMBEDDED_INPUTS").is_none() {
+        return;
+    }
+
+    let input_directory = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("input/2024");
+    println!("cargo:rerun-if-changed={}", input_directory.display());
+    let mut table = String::from("static INPUTS: &[(u32, &str)] = &[\n");
+    for day in 1..=25 {
+        let path = input_directory.join(format!("day{day}.txt"));
+        if path.is_file() {
+            println!("cargo:rerun-if-changed={}", path.display());
+            writeln!(
+                table,
+                "    ({day}, include_str!({:?})),",
+                path.display().to_string()
+            )
+            .unwrap();
+        }
+    }
+    table.push_str("];\n");
+    fs::write(
+        Path::new(&env::var("OUT_DIR").unwrap()).join("inputs.rs"),
+        table,
+    )
+    .unwrap();
+}
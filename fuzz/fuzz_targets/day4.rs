@@ -0,0 +1,11 @@
+#![no_main]
+
+use aoc_2024::day4::{input_generator, part1, part2};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(parsed) = input_generator(input) {
+        let _ = part1(&parsed);
+        let _ = part2(&parsed);
+    }
+});
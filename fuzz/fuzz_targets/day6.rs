@@ -0,0 +1,12 @@
+#![no_main]
+
+use aoc_2024::day6::{input_generator, part1, part2, part2_jump};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(parsed) = input_generator(input) {
+        let _ = part1(&parsed);
+        let _ = part2(&parsed);
+        let _ = part2_jump(&parsed);
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use aoc_2024::day7::{input_generator, part1, part1_reverse, part2, part2_reverse};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(parsed) = input_generator(input) {
+        let _ = part1(&parsed);
+        let _ = part2(&parsed);
+        let _ = part1_reverse(&parsed);
+        let _ = part2_reverse(&parsed);
+    }
+});
@@ -0,0 +1,161 @@
+//! A global allocator that counts what gets allocated, used with the `count-allocations`
+//! feature to see how much each generator and part actually puts on the heap.
+//!
+//! The counts are shared by every thread, which means work a part hands to rayon is
+//! included, but also that anything else running at the same time is too.
+
+use crate::solution::DynSolution;
+use crate::ParseError;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+static IN_USE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn allocated(&self, size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(size, Ordering::Relaxed);
+        let in_use = IN_USE.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(in_use, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            self.allocated(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc_zeroed(layout);
+        if !pointer.is_null() {
+            self.allocated(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        IN_USE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    // A reallocation counts as a new allocation of the new size, with the old one freed.
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = System.realloc(pointer, layout, new_size);
+        if !new_pointer.is_null() {
+            IN_USE.fetch_sub(layout.size(), Ordering::Relaxed);
+            self.allocated(new_size);
+        }
+        new_pointer
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Usage {
+    pub allocations: usize,
+    pub bytes: usize,
+    /// The most heap in use at once, over and above what was already in use beforehand.
+    pub peak: usize,
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} allocations, {} bytes, peak {} bytes",
+            self.allocations, self.bytes, self.peak
+        )
+    }
+}
+
+/// Runs `f`, counting everything allocated while it does.
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, Usage) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+    let in_use = IN_USE.load(Ordering::Relaxed);
+    PEAK.store(in_use, Ordering::Relaxed);
+    let result = f();
+    let usage = Usage {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        bytes: BYTES.load(Ordering::Relaxed) - bytes,
+        peak: PEAK.load(Ordering::Relaxed).saturating_sub(in_use),
+    };
+    (result, usage)
+}
+
+/// What a day's generator and each implementation of its parts allocate. The generator's
+/// usage always includes one allocation the size of the parsed input, since the registry
+/// keeps it boxed, so inputs stored in arrays show up there at their full size.
+pub struct Profile {
+    pub day: u32,
+    pub generator: Usage,
+    /// By part and implementation name.
+    pub parts: Vec<(u32, &'static str, Usage)>,
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Day {}\n\tgenerator: {}", self.day, self.generator)?;
+        for (part, name, usage) in &self.parts {
+            write!(f, "\n\tpart {part} {name}: {usage}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `input` and runs every implementation of both parts on it, measuring each.
+/// The parsed input is kept for the parts, so it doesn't count towards their usage.
+pub fn profile(solution: &dyn DynSolution, input: &str) -> Result<Profile, ParseError> {
+    let (parsed, generator) = measure(|| solution.parse(input));
+    let parsed = parsed?;
+    let mut parts = Vec::new();
+    for part in [1, 2] {
+        for name in solution.implementations(part) {
+            let (_, usage) = measure(|| solution.run(part, name, &*parsed));
+            parts.push((part, name, usage));
+        }
+    }
+    Ok(Profile {
+        day: solution.day(),
+        generator,
+        parts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution;
+
+    // Other tests allocate on their own threads at the same time, so these are only lower
+    // bounds.
+    #[test]
+    fn test_measure() {
+        let (vector, usage) = measure(|| {
+            let mut vector = Vec::with_capacity(1000);
+            vector.extend(0..1000_u32);
+            vector.push(1000);
+            vector
+        });
+        assert_eq!(vector.len(), 1001);
+        assert!(usage.allocations >= 2);
+        assert!(usage.bytes >= 4000 + 4004);
+        assert!(usage.peak >= 4004);
+
+        let profile = profile(solution::get(7).unwrap(), "190: 10 19").unwrap();
+        assert_eq!(profile.parts.len(), 4);
+        assert!(profile.to_string().starts_with("Day 7\n\tgenerator: "));
+    }
+}
@@ -0,0 +1,120 @@
+use serde::Serialize;
+use std::fmt;
+
+/// A puzzle answer in a form that every part can return, regardless of whether the
+/// puzzle asks for a number, some text or a picture drawn on a grid.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Answer {
+    Integer(i64),
+    BigInteger(i128),
+    String(String),
+    Grid(Vec<String>),
+}
+
+impl Answer {
+    /// Snapshot a grid one row at a time, e.g. from a `lines()` iterator.
+    pub fn grid<I, S>(rows: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::Grid(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Integer(value) => write!(f, "{value}"),
+            Self::BigInteger(value) => write!(f, "{value}"),
+            Self::String(value) => write!(f, "{value}"),
+            // start grids on their own line so the rows line up when printed after a label.
+            Self::Grid(rows) => rows.iter().try_for_each(|row| write!(f, "\n{row}")),
+        }
+    }
+}
+
+// Integers are always stored in the smallest variant that holds them so that the
+// same number compares equal no matter which integer type a part returned.
+impl From<i128> for Answer {
+    fn from(value: i128) -> Self {
+        match i64::try_from(value) {
+            Ok(value) => Self::Integer(value),
+            Err(_) => Self::BigInteger(value),
+        }
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($integer:ty),*) => {
+        $(
+            impl From<$integer> for Answer {
+                fn from(value: $integer) -> Self {
+                    i128::from(value).into()
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        (value as u64).into()
+    }
+}
+
+impl From<isize> for Answer {
+    fn from(value: isize) -> Self {
+        (value as i64).into()
+    }
+}
+
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integer_types_compare_equal() {
+        assert_eq!(Answer::from(41_u32), Answer::from(41_usize));
+        assert_eq!(Answer::from(41_u64), Answer::Integer(41));
+        assert_eq!(Answer::from(u64::MAX), Answer::BigInteger(u64::MAX as i128));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Answer::from(11387_u64).to_string(), "11387");
+        assert_eq!(Answer::from("6,4,9").to_string(), "6,4,9");
+        assert_eq!(Answer::grid(["#.", ".#"]).to_string(), "\n#.\n.#");
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            Answer::from(143_u32).to_json(),
+            r#"{"type":"integer","value":143}"#
+        );
+        assert_eq!(
+            Answer::grid(["#.", ".#"]).to_json(),
+            r##"{"type":"grid","value":["#.",".#"]}"##
+        );
+    }
+}
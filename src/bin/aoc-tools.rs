@@ -0,0 +1,512 @@
+use aoc_2024::conformance::{self, Conformance};
+use aoc_2024::day3;
+use aoc_2024::day5;
+use aoc_2024::determinism::{self, Options};
+use aoc_2024::fetch::{self, Fetcher};
+use aoc_2024::generate;
+use aoc_2024::progress::{self, Progress};
+use aoc_2024::scaffold;
+use aoc_2024::solution;
+use aoc_2024::watch::{self, Run};
+use notify::{RecursiveMode, Watcher};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::time::Duration;
+
+const USAGE: &str = "usage: aoc-tools [-v...] <command> [args]
+
+    -v, -vv, -vvv              log spans with timings, events from the solvers or everything
+                               to stderr, which needs the tracing feature
+
+commands:
+    fetch <day>...             download and cache the input and example for each day
+    new-day <day> [title]      create the source file, module and input placeholder for a day
+    readme [--verify] [--timings]
+                               regenerate the progress table in README.md, optionally running
+                               the tests and the solutions to check parts and time them
+    generate <day> [name=value]... [--seed <n>] [--answers]
+                               print a random input for a day, sized by the named parameters,
+                               and its answers to stderr if they're known
+    rules [--json] [--update <pages>] [input]
+                               print the day 5 page ordering rules from the input (by default
+                               input/2024/day5.txt) as a Graphviz digraph or JSON, optionally
+                               only between the pages of an update like 75,97,47
+    near-misses [input]        list everything in the day 3 input (by default input/2024/day3.txt)
+                               that starts like an instruction but isn't one, and why, then
+                               count them by instruction and reason
+    check [day]...             run every implementation of each part on the day's input (by
+                               default every day), timing them and failing if any disagree
+    validate [day]...          check that each day's input (by default every day's) follows the
+                               rules in the puzzle description, which AOC_CONFORMANCE=strict
+                               enforces whenever an input is parsed
+    determinism [--rounds <n>] [--seed <n>] [--generated <n>] [day]...
+                               run every implementation of each part on the day's input (by
+                               default every day) and n generated ones, over and over with
+                               different thread counts and orders, failing if any answer changes
+    allocations [day]...       report the allocations, bytes allocated and peak heap usage of each
+                               day's generator and every implementation of its parts (by
+                               default every day), which needs the count-allocations feature
+    watch --day <day>          re-run a day's tests and implementations whenever its source or
+                               input changes, showing how the answers and timings changed";
+
+fn parse_days(args: &[String]) -> Result<Vec<u32>, String> {
+    if args.is_empty() {
+        return Err("expected at least one day".to_owned());
+    }
+    args.iter()
+        .map(|arg| match arg.parse() {
+            Ok(day @ 1..=25) => Ok(day),
+            _ => Err(format!("'{arg}' isn't a day between 1 and 25")),
+        })
+        .collect()
+}
+
+fn fetch(args: &[String]) -> Result<(), String> {
+    let days = parse_days(args)?;
+    let session = fetch::find_session().map_err(|error| error.to_string())?;
+    let mut fetcher = Fetcher::new(session);
+    for day in days {
+        let input = fetcher
+            .fetch_input(day)
+            .map_err(|error| error.to_string())?;
+        println!("Day {day} input: {}", input.display());
+        match fetcher.fetch_example(day) {
+            Ok(example) => println!("Day {day} example: {}", example.display()),
+            Err(error) => eprintln!("Day {day} example: {error}"),
+        }
+    }
+    Ok(())
+}
+
+fn new_day(args: &[String]) -> Result<(), String> {
+    let (day, title) = match args {
+        [day] => (day, None),
+        [day, title] => (day, Some(title.as_str())),
+        _ => return Err("expected a day and optionally its title".to_owned()),
+    };
+    let day = parse_days(std::slice::from_ref(day))?[0];
+    let changes =
+        scaffold::new_day(Path::new("."), day, title).map_err(|error| error.to_string())?;
+    if changes.is_empty() {
+        println!("Day {day} is already set up");
+    }
+    for change in changes {
+        println!("{change}");
+    }
+    Ok(())
+}
+
+// Runs cargo with the given arguments, returning stdout whether or not it succeeded
+// since failing tests are something the caller wants to know about.
+fn cargo_output(args: &[&str]) -> Result<String, String> {
+    let output = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()))
+        .args(args)
+        .output()
+        .map_err(|error| error.to_string())?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn readme(args: &[String]) -> Result<(), String> {
+    let mut verify = false;
+    let mut timings = false;
+    for arg in args {
+        match arg.as_str() {
+            "--verify" => verify = true,
+            "--timings" => timings = true,
+            _ => return Err(format!("unknown option '{arg}'")),
+        }
+    }
+
+    let readme = fs::read_to_string("README.md").map_err(|error| error.to_string())?;
+    let progress = Progress {
+        implemented: progress::discover_parts(Path::new("src"))
+            .map_err(|error| error.to_string())?,
+        titles: progress::parse_titles(&readme),
+        test_results: if verify {
+            Some(progress::parse_test_results(&cargo_output(&[
+                "test",
+                "--release",
+                "--lib",
+            ])?))
+        } else {
+            None
+        },
+        timings: if timings {
+            progress::parse_timings(&cargo_output(&["run", "--release", "--bin", "aoc-2024"])?)
+        } else {
+            Default::default()
+        },
+    };
+    let readme = progress::replace_table(&readme, &progress.render_table()).ok_or(format!(
+        "README.md is missing the {} and {} markers",
+        progress::START_MARKER,
+        progress::END_MARKER
+    ))?;
+    fs::write("README.md", readme).map_err(|error| error.to_string())
+}
+
+fn generate(args: &[String]) -> Result<(), String> {
+    let (day, args) = args.split_first().ok_or("expected a day")?;
+    let day = parse_days(std::slice::from_ref(day))?[0];
+    let mut seed = 0;
+    let mut show_answers = false;
+    let mut parameters = HashMap::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                let value = args.next().ok_or("expected a seed after --seed")?;
+                seed = value
+                    .parse()
+                    .map_err(|_| format!("'{value}' isn't a valid seed"))?;
+            }
+            "--answers" => show_answers = true,
+            _ => {
+                let (name, value) = arg
+                    .split_once('=')
+                    .ok_or(format!("expected name=value, got '{arg}'"))?;
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("'{value}' isn't a valid size for {name}"))?;
+                parameters.insert(name.to_owned(), value);
+            }
+        }
+    }
+
+    let generated = generate::generate(day, seed, &parameters)?;
+    print!("{}", generated.input);
+    if show_answers {
+        match generated.answers {
+            Some((part1, part2)) => {
+                eprintln!("Day {day} - Part 1: {part1}\nDay {day} - Part 2: {part2}")
+            }
+            None => eprintln!("Day {day} answers aren't known for generated inputs"),
+        }
+    }
+    Ok(())
+}
+
+fn rules(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut update = None;
+    let mut path = "input/2024/day5.txt";
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--update" => {
+                let pages = args.next().ok_or("expected pages after --update")?;
+                let pages = pages
+                    .split(',')
+                    .map(|page| {
+                        page.trim()
+                            .parse()
+                            .map_err(|_| format!("'{page}' isn't a valid page"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                update = Some(pages);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{arg}'")),
+            _ => path = arg,
+        }
+    }
+
+    let input = fs::read_to_string(path).map_err(|error| format!("{path}: {error}"))?;
+    let (rules, _) = day5::parse_input(&input).map_err(|error| format!("{path}: {error}"))?;
+    if json {
+        println!("{}", rules.to_json(update.as_deref()));
+    } else {
+        print!("{}", rules.to_dot(update.as_deref()));
+    }
+    Ok(())
+}
+
+fn near_misses(args: &[String]) -> Result<(), String> {
+    let path = match args {
+        [] => "input/2024/day3.txt",
+        [path] => path.as_str(),
+        _ => return Err("expected at most one input".to_owned()),
+    };
+    let input = fs::read_to_string(path).map_err(|error| format!("{path}: {error}"))?;
+    let near_misses = day3::near_misses(&input);
+    let mut counts = BTreeMap::new();
+    for near_miss in &near_misses {
+        println!("{near_miss}");
+        *counts
+            .entry((near_miss.instruction, near_miss.reason))
+            .or_insert(0) += 1;
+    }
+    println!("\n{} near misses", near_misses.len());
+    for ((instruction, reason), count) in counts {
+        println!("\t{}: {reason}: {count}", instruction.name());
+    }
+    Ok(())
+}
+
+fn solution_days(args: &[String]) -> Result<Vec<u32>, String> {
+    if args.is_empty() {
+        Ok(solution::SOLUTIONS
+            .iter()
+            .map(|solution| solution.day())
+            .collect())
+    } else {
+        parse_days(args)
+    }
+}
+
+fn read_input(day: u32) -> Result<(&'static dyn solution::DynSolution, String), String> {
+    let solution = solution::get(day).ok_or(format!("Day {day} isn't solved yet"))?;
+    let path = format!("input/2024/day{day}.txt");
+    let input = fs::read_to_string(&path).map_err(|error| format!("{path}: {error}"))?;
+    Ok((solution, input))
+}
+
+fn check(args: &[String]) -> Result<(), String> {
+    let mut disagreements = Vec::new();
+    for day in solution_days(args)? {
+        let (solution, input) = read_input(day)?;
+        for check in solution::cross_check(solution, &input)
+            .map_err(|error| format!("input/2024/day{day}.txt: {error}"))?
+        {
+            println!("{check}\n");
+            if !check.agrees() {
+                disagreements.push(format!("day {} part {}", check.day, check.part));
+            }
+        }
+    }
+    if disagreements.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "implementations disagree for {}",
+            disagreements.join(", ")
+        ))
+    }
+}
+
+fn validate(args: &[String]) -> Result<(), String> {
+    let mut failures = 0;
+    for day in solution_days(args)? {
+        let (solution, input) = read_input(day)?;
+        match conformance::with_conformance(Conformance::Strict, || solution.parse(&input)) {
+            Ok(_) => println!("Day {day}: ok"),
+            Err(error) => {
+                println!("Day {day}: input/2024/day{day}.txt: {error}");
+                failures += 1;
+            }
+        }
+    }
+    match failures {
+        0 => Ok(()),
+        _ => Err(format!("{failures} inputs break the puzzle rules")),
+    }
+}
+
+fn option_value<'a, T: FromStr>(
+    args: &mut impl Iterator<Item = &'a String>,
+    option: &str,
+) -> Result<T, String> {
+    let value = args
+        .next()
+        .ok_or(format!("expected a number after {option}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("'{value}' isn't a valid number for {option}"))
+}
+
+fn determinism(args: &[String]) -> Result<(), String> {
+    let mut options = Options::default();
+    let mut generated = 0;
+    let mut days = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rounds" => options.rounds = option_value(&mut args, arg)?,
+            "--seed" => options.seed = option_value(&mut args, arg)?,
+            "--generated" => generated = option_value(&mut args, arg)?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{arg}'")),
+            _ => days.push(arg.clone()),
+        }
+    }
+
+    let mut changing = Vec::new();
+    for day in solution_days(&days)? {
+        let (solution, input) = read_input(day)?;
+        let mut inputs = vec![input];
+        for seed in 0..generated {
+            inputs.push(generate::generate(day, options.seed + seed, &HashMap::new())?.input);
+        }
+        let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+        for result in determinism::check(solution, &inputs, &options)
+            .map_err(|error| format!("day {day}: {error}"))?
+        {
+            println!("{result}");
+            if !result.is_deterministic() {
+                changing.push(format!(
+                    "day {} part {} {} on input {}",
+                    result.day, result.part, result.name, result.input
+                ));
+            }
+        }
+        println!();
+    }
+    if changing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "answers changed between runs for {}",
+            changing.join(", ")
+        ))
+    }
+}
+
+#[cfg(feature = "count-allocations")]
+fn allocations(args: &[String]) -> Result<(), String> {
+    use aoc_2024::allocations;
+
+    for day in solution_days(args)? {
+        let (solution, input) = read_input(day)?;
+        let profile = allocations::profile(solution, &input)
+            .map_err(|error| format!("input/2024/day{day}.txt: {error}"))?;
+        println!("{profile}\n");
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "count-allocations"))]
+fn allocations(_: &[String]) -> Result<(), String> {
+    Err("allocations are only counted when built with --features count-allocations".to_owned())
+}
+
+// Runs the day's tests and then every implementation on its input, which rebuilds anything
+// that changed first. Build errors go straight to the terminal since there's nothing else
+// to show.
+fn run_day(day: u32) -> Result<Run, String> {
+    let filter = format!("day{day}::");
+    let tests = cargo_output(&["test", "--release", "--lib", &filter])?;
+    let output = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()))
+        .args(["run", "--release", "--bin", "aoc-tools", "--", "check"])
+        .arg(day.to_string())
+        .output()
+        .map_err(|error| error.to_string())?;
+    let answers = watch::parse_answers(&String::from_utf8_lossy(&output.stdout));
+    if answers.is_empty() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(Run {
+        tests: watch::parse_tests(&tests, day),
+        answers,
+    })
+}
+
+fn watch(args: &[String]) -> Result<(), String> {
+    let day = match args {
+        [flag, day] if flag == "--day" => parse_days(std::slice::from_ref(day))?[0],
+        _ => return Err("expected --day <day>".to_owned()),
+    };
+    let source = format!("src/day{day}.rs");
+    let input = format!("input/2024/day{day}.txt");
+
+    // the directories are watched rather than the files themselves, since editors often
+    // save by replacing the file.
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(|error| error.to_string())?;
+    for directory in ["src", "input/2024"] {
+        watcher
+            .watch(Path::new(directory), RecursiveMode::NonRecursive)
+            .map_err(|error| format!("{directory}: {error}"))?;
+    }
+    let is_relevant = |event: &notify::Event| {
+        !event.kind.is_access()
+            && event
+                .paths
+                .iter()
+                .any(|path| path.ends_with(&source) || path.ends_with(&input))
+    };
+
+    let mut previous = None;
+    loop {
+        println!("Running day {day}...");
+        let run = run_day(day)?;
+        print!("{}", run.report(previous.as_ref()));
+        println!("Watching {source} and {input} for changes");
+        previous = Some(run);
+
+        loop {
+            match receiver.recv() {
+                Ok(Ok(event)) if is_relevant(&event) => break,
+                Ok(Ok(_)) => {}
+                Ok(Err(error)) => return Err(error.to_string()),
+                Err(_) => return Ok(()),
+            }
+        }
+        // a save often comes as several events, so wait for them to settle down.
+        while receiver.recv_timeout(Duration::from_millis(200)).is_ok() {}
+        println!();
+    }
+}
+
+// Counts the leading -v flags, so -v -v and -vv are both 2.
+fn parse_verbosity(args: &[String]) -> (u8, &[String]) {
+    let mut verbosity = 0;
+    let mut rest = args;
+    while let Some((flag, remaining)) = rest.split_first() {
+        match flag.strip_prefix('-') {
+            Some(vs) if !vs.is_empty() && vs.bytes().all(|byte| byte == b'v') => {
+                verbosity += vs.len() as u8;
+                rest = remaining;
+            }
+            _ => break,
+        }
+    }
+    (verbosity, rest)
+}
+
+#[cfg(feature = "tracing")]
+fn init_tracing(verbosity: u8) -> Result<(), String> {
+    aoc_2024::trace::init(verbosity);
+    Ok(())
+}
+
+#[cfg(not(feature = "tracing"))]
+fn init_tracing(verbosity: u8) -> Result<(), String> {
+    if verbosity == 0 {
+        Ok(())
+    } else {
+        Err("-v only logs anything when built with --features tracing".to_owned())
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (verbosity, args) = parse_verbosity(&args);
+    let result = aoc_2024::configure_from_env()
+        .and_then(|()| init_tracing(verbosity))
+        .and_then(|()| match args.first().map(String::as_str) {
+            Some("fetch") => fetch(&args[1..]),
+            Some("new-day") => new_day(&args[1..]),
+            Some("readme") => readme(&args[1..]),
+            Some("generate") => generate(&args[1..]),
+            Some("rules") => rules(&args[1..]),
+            Some("near-misses") => near_misses(&args[1..]),
+            Some("check") => check(&args[1..]),
+            Some("validate") => validate(&args[1..]),
+            Some("determinism") => determinism(&args[1..]),
+            Some("allocations") => allocations(&args[1..]),
+            Some("watch") => watch(&args[1..]),
+            _ => Err(USAGE.to_owned()),
+        });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
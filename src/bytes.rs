@@ -0,0 +1,204 @@
+//! Shared preprocessing for puzzle inputs, so every day copes with the same variations in
+//! how an input file was saved or pasted into a test:
+//!
+//! - a leading byte order mark is ignored,
+//! - lines may end in either `\n` or `\r\n`,
+//! - whitespace around the whole input and around each line is ignored, so examples can
+//!   be indented to line up with the code around them,
+//! - sections are separated by lines that are blank once trimmed.
+//!
+//! Line numbers in errors count from the first line of the trimmed input.
+//!
+//! Puzzle inputs are plain ASCII, so the generators work on `&[u8]` slices of the input to
+//! avoid re-checking UTF-8 and to keep grids at a byte per cell. Everything here borrows
+//! from the input.
+
+const BYTE_ORDER_MARK: &[u8] = b"\xef\xbb\xbf";
+
+/// The input without any byte order mark or surrounding whitespace.
+pub fn trim(input: &[u8]) -> &[u8] {
+    input
+        .strip_prefix(BYTE_ORDER_MARK)
+        .unwrap_or(input)
+        .trim_ascii()
+}
+
+/// Each line of the trimmed input with its own surrounding whitespace removed, which also
+/// takes care of `\r\n` line endings.
+pub fn lines(input: &[u8]) -> Lines<'_> {
+    let input = trim(input);
+    Lines {
+        remaining: (!input.is_empty()).then_some(input),
+    }
+}
+
+/// The iterator returned by [`lines`].
+#[derive(Clone, Debug)]
+pub struct Lines<'input> {
+    remaining: Option<&'input [u8]>,
+}
+
+impl<'input> Iterator for Lines<'input> {
+    type Item = &'input [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.remaining?;
+        let (line, rest) = match memchr::memchr(b'\n', remaining) {
+            Some(index) => (&remaining[..index], Some(&remaining[index + 1..])),
+            None => (remaining, None),
+        };
+        self.remaining = rest;
+        Some(line.trim_ascii())
+    }
+}
+
+/// A run of lines with no blank lines in it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Section<'input> {
+    pub first_line: usize,
+    pub text: &'input [u8],
+}
+
+/// Splits the trimmed input wherever there are one or more blank lines. The text of each
+/// section is untrimmed, so it should still be read with [`lines`].
+pub fn sections(input: &[u8]) -> Vec<Section<'_>> {
+    let input = trim(input);
+    let mut sections = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    for (index, line) in input.split_inclusive(|&byte| byte == b'\n').enumerate() {
+        match (line.trim_ascii().is_empty(), start) {
+            (false, None) => start = Some((index + 1, offset)),
+            (true, Some((first_line, start_offset))) => {
+                sections.push(Section {
+                    first_line,
+                    text: &input[start_offset..offset],
+                });
+                start = None;
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+    if let Some((first_line, start_offset)) = start {
+        sections.push(Section {
+            first_line,
+            text: &input[start_offset..],
+        });
+    }
+    sections
+}
+
+/// The parts of a line separated by runs of whitespace, like [`str::split_whitespace`].
+pub fn fields(line: &[u8]) -> impl Iterator<Item = &[u8]> + Clone {
+    line.split(u8::is_ascii_whitespace)
+        .filter(|field| !field.is_empty())
+}
+
+/// Splits a line around the first occurrence of `separator`.
+pub fn split_once(line: &[u8], separator: u8) -> Option<(&[u8], &[u8])> {
+    let index = line.iter().position(|&byte| byte == separator)?;
+    Some((&line[..index], &line[index + 1..]))
+}
+
+/// Integers that can be read straight from ASCII digits.
+pub trait Integer: Sized {
+    /// Reads the whole of `digits` as a number, or `None` if there's anything other than
+    /// digits (and a leading minus sign for signed types) or the number doesn't fit.
+    fn from_digits(digits: &[u8]) -> Option<Self>;
+}
+
+macro_rules! accumulate {
+    ($type:ty, $digits:expr, $combine:ident) => {{
+        let digits: &[u8] = $digits;
+        if digits.is_empty() {
+            return None;
+        }
+        let mut value: $type = 0;
+        for &digit in digits {
+            if !digit.is_ascii_digit() {
+                return None;
+            }
+            value = value.checked_mul(10)?.$combine((digit - b'0') as $type)?;
+        }
+        Some(value)
+    }};
+}
+
+macro_rules! impl_unsigned {
+    ($($type:ty),*) => {
+        $(impl Integer for $type {
+            fn from_digits(digits: &[u8]) -> Option<Self> {
+                accumulate!($type, digits, checked_add)
+            }
+        })*
+    };
+}
+
+macro_rules! impl_signed {
+    ($($type:ty),*) => {
+        $(impl Integer for $type {
+            fn from_digits(digits: &[u8]) -> Option<Self> {
+                match digits.strip_prefix(b"-") {
+                    Some(digits) => accumulate!($type, digits, checked_sub),
+                    None => accumulate!($type, digits, checked_add),
+                }
+            }
+        })*
+    };
+}
+
+impl_unsigned!(u8, u16, u32, u64, u128, usize);
+impl_signed!(i8, i16, i32, i64, i128, isize);
+
+/// Parses the whole of `digits` as a number.
+pub fn parse<T: Integer>(digits: &[u8]) -> Option<T> {
+    T::from_digits(digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        const INPUT: &[u8] = b"\xef\xbb\xbf\r\n  3   4\r\n\t4   3  \r\n\r\n";
+        assert_eq!(lines(INPUT).collect::<Vec<_>>(), [b"3   4", b"4   3"]);
+        assert_eq!(lines(b" \n ").count(), 0);
+        assert_eq!(
+            fields(b" 3   4\t").collect::<Vec<_>>(),
+            [b"3".as_slice(), b"4"]
+        );
+        assert_eq!(
+            split_once(b"47|53", b'|'),
+            Some((b"47".as_slice(), b"53".as_slice()))
+        );
+    }
+
+    #[test]
+    fn test_sections() {
+        const INPUT: &[u8] =
+            b"\xef\xbb\xbf\r\n    47|53\r\n    97|13\r\n    \r\n  \r\n    75,47\r\n";
+        let sections = sections(INPUT);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].first_line, 1);
+        assert_eq!(
+            lines(sections[0].text).collect::<Vec<_>>(),
+            [b"47|53", b"97|13"]
+        );
+        assert_eq!(sections[1].first_line, 5);
+        assert_eq!(lines(sections[1].text).collect::<Vec<_>>(), [b"75,47"]);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse::<u32>(b"4294967295"), Some(u32::MAX));
+        assert_eq!(parse::<u32>(b"4294967296"), None);
+        assert_eq!(parse::<i8>(b"-128"), Some(i8::MIN));
+        assert_eq!(parse::<i8>(b"128"), None);
+        assert_eq!(parse::<u8>(b"-1"), None);
+        assert_eq!(parse::<u8>(b""), None);
+        assert_eq!(parse::<i8>(b"-"), None);
+        assert_eq!(parse::<u64>(b"1 2"), None);
+    }
+}
@@ -0,0 +1,139 @@
+//! Whether the generators hold inputs to the rules in the puzzle descriptions, like rows of
+//! a grid all being the same width, or accept anything they can make sense of. Lenient is
+//! the default, and strict can be chosen with the `AOC_CONFORMANCE` environment variable,
+//! which is read the first time a generator runs unless [`configure`] was called before then:
+//!
+//! ```text
+//! AOC_CONFORMANCE=strict
+//! ```
+
+use crate::bytes;
+use crate::ParseError;
+use std::cell::Cell;
+use std::sync::OnceLock;
+
+pub const ENVIRONMENT_VARIABLE: &str = "AOC_CONFORMANCE";
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Conformance {
+    #[default]
+    Lenient,
+    /// Inputs that break the puzzle's rules are rejected with
+    /// [`ParseError::Nonconforming`](crate::ParseError::Nonconforming).
+    Strict,
+}
+
+impl Conformance {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim() {
+            "lenient" => Ok(Self::Lenient),
+            "strict" => Ok(Self::Strict),
+            text => Err(format!("'{text}' should be strict or lenient")),
+        }
+    }
+
+    /// The conformance from `AOC_CONFORMANCE`, which is lenient when it isn't set.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var(ENVIRONMENT_VARIABLE) {
+            Ok(text) => {
+                Self::parse(&text).map_err(|error| format!("{ENVIRONMENT_VARIABLE}: {error}"))
+            }
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+thread_local! {
+    static OVERRIDE: Cell<Option<Conformance>> = const { Cell::new(None) };
+}
+
+/// Runs `f` with `conformance` in place of the one given to [`configure`] for any
+/// generators it calls on this thread.
+pub fn with_conformance<R>(conformance: Conformance, f: impl FnOnce() -> R) -> R {
+    let previous = OVERRIDE.replace(Some(conformance));
+    let result = f();
+    OVERRIDE.set(previous);
+    result
+}
+
+static CONFORMANCE: OnceLock<Conformance> = OnceLock::new();
+
+/// Sets the conformance for every generator not run inside [`with_conformance`], in place of
+/// the one from `AOC_CONFORMANCE`. It has no effect once a generator has run.
+pub fn configure(conformance: Conformance) {
+    let _ = CONFORMANCE.set(conformance);
+}
+
+/// The conformance generators on this thread should parse with. An `AOC_CONFORMANCE` that's
+/// neither strict nor lenient is reported the first time it's read and then treated as lenient.
+pub fn current() -> Conformance {
+    OVERRIDE.get().unwrap_or_else(|| {
+        *CONFORMANCE.get_or_init(|| {
+            Conformance::from_env().unwrap_or_else(|error| {
+                eprintln!("{error}, so it's being ignored");
+                Conformance::default()
+            })
+        })
+    })
+}
+
+pub fn is_strict() -> bool {
+    current() == Conformance::Strict
+}
+
+/// Checks that every row of a grid is as wide as the first and only has `allowed` in it.
+pub(crate) fn check_grid(input: &[u8], allowed: &[u8]) -> Result<(), ParseError> {
+    let mut first_width = None;
+    for (index, row) in bytes::lines(input).enumerate() {
+        let nonconforming = |reason| ParseError::Nonconforming {
+            line: Some(index + 1),
+            reason,
+        };
+        let width = *first_width.get_or_insert(row.len());
+        if row.len() != width {
+            return Err(nonconforming(format!(
+                "row is {} wide, but the first row is {width}",
+                row.len()
+            )));
+        }
+        if let Some(column) = row.iter().position(|byte| !allowed.contains(byte)) {
+            return Err(nonconforming(format!(
+                "column {}: '{}' should be one of {}",
+                column + 1,
+                row[column].escape_ascii(),
+                allowed.escape_ascii()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conformance() {
+        assert_eq!(Conformance::parse("strict"), Ok(Conformance::Strict));
+        assert_eq!(Conformance::parse(" lenient\n"), Ok(Conformance::Lenient));
+        assert!(Conformance::parse("pedantic").is_err());
+        assert!(with_conformance(Conformance::Strict, is_strict));
+        assert!(!with_conformance(Conformance::Lenient, is_strict));
+    }
+
+    #[test]
+    fn test_check_grid() {
+        assert_eq!(check_grid(b"XM\nAS\n", b"XMAS"), Ok(()));
+        assert_eq!(
+            check_grid(b"XM\nASX", b"XMAS"),
+            Err(ParseError::Nonconforming {
+                line: Some(2),
+                reason: "row is 3 wide, but the first row is 2".to_owned()
+            })
+        );
+        assert_eq!(
+            check_grid(b"XM\nAs", b"XMAS").unwrap_err().to_string(),
+            "line 2: column 2: 's' should be one of XMAS"
+        );
+    }
+}
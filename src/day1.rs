@@ -1,67 +1,137 @@
+use crate::bytes;
+use crate::error::{parse_number, Overflow, ParseError};
+use crate::parallelism::{self, Mode};
+use crate::solution::Solution;
+use crate::trace;
+use crate::Answer;
 use aoc_runner_derive::aoc;
+use rayon::prelude::*;
 use std::collections::HashMap;
 
-#[aoc(day1, part1)]
-pub fn part1(input: &str) -> u32 {
-    let lines = input
-        .trim()
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty());
+fn parse_line(line: &[u8], line_number: usize) -> Result<(u32, u32), ParseError> {
+    let mut line = bytes::fields(line);
+    match (line.next(), line.next()) {
+        (Some(first), Some(second)) => Ok((
+            parse_number(first, line_number)?,
+            parse_number(second, line_number)?,
+        )),
+        _ => Err(ParseError::Malformed {
+            line: line_number,
+            expected: "two location IDs",
+        }),
+    }
+}
+
+/// The two lists of location IDs, in the order they were written down.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LocationLists {
+    pub left: Vec<u32>,
+    pub right: Vec<u32>,
+}
+
+pub fn parse_lists(input: &str) -> Result<LocationLists, ParseError> {
+    trace::span!("generator", day = 1);
+    let mut lines = bytes::lines(input.as_bytes())
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty());
     let count = lines.clone().count();
-    let (mut left, mut right) = lines.fold(
+    let (left, right) = lines.try_fold(
         (Vec::<u32>::with_capacity(count), Vec::with_capacity(count)),
-        |(mut left, mut right), line| {
-            let mut line = line.split_whitespace();
-            let (first, second) = (line.next().unwrap(), line.next().unwrap());
-            left.push(first.parse().unwrap());
-            right.push(second.parse().unwrap());
-            (left, right)
+        |(mut left, mut right), (index, line)| {
+            let (first, second) = parse_line(line, index + 1)?;
+            left.push(first);
+            right.push(second);
+            Ok::<_, ParseError>((left, right))
         },
-    );
-    left.sort();
-    right.sort();
-    left.into_iter()
-        .zip(right)
-        .map(|(left, right)| {
-            if left < right {
-                right - left
-            } else {
-                left - right
-            }
-        })
-        .sum()
+    )?;
+    Ok(LocationLists { left, right })
+}
+
+fn total_distance(
+    LocationLists {
+        mut left,
+        mut right,
+    }: LocationLists,
+) -> Result<u32, Overflow> {
+    trace::span!("part", day = 1, part = 1);
+    parallelism::run(1, 1, Mode::Sequential, |parallel| {
+        if parallel {
+            rayon::join(|| left.par_sort(), || right.par_sort());
+            left.par_iter()
+                .zip(&right)
+                .map(|(left, right)| Some(left.abs_diff(*right)))
+                .try_reduce(|| 0, u32::checked_add)
+        } else {
+            left.sort();
+            right.sort();
+            left.iter()
+                .zip(&right)
+                .map(|(left, right)| left.abs_diff(*right))
+                .try_fold(0, u32::checked_add)
+        }
+    })
+    .ok_or(Overflow)
+}
+
+fn similarity_score(lists: &LocationLists) -> Result<u32, Overflow> {
+    trace::span!("part", day = 1, part = 2);
+    let mut right = HashMap::<u32, u32>::with_capacity(lists.right.len());
+    for num in &lists.right {
+        *right.entry(*num).or_insert(0) += 1;
+    }
+    let similarity = |num: &u32| num.checked_mul(*right.get(num).unwrap_or(&0));
+    parallelism::run(1, 2, Mode::Sequential, |parallel| {
+        if parallel {
+            lists
+                .left
+                .par_iter()
+                .map(similarity)
+                .try_reduce(|| 0, u32::checked_add)
+        } else {
+            lists
+                .left
+                .iter()
+                .map(similarity)
+                .try_fold(0, |total: u32, score| total.checked_add(score?))
+        }
+    })
+    .ok_or(Overflow)
+}
+
+#[aoc(day1, part1)]
+pub fn part1(input: &str) -> Result<u32, ParseError> {
+    Ok(total_distance(parse_lists(input)?)?)
 }
 
 #[aoc(day1, part2)]
-pub fn part2(input: &str) -> u32 {
-    let lines = input
-        .trim()
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty());
-    let count = lines.clone().count();
-    let (left, right) = lines.fold(
-        (
-            Vec::<u32>::with_capacity(count),
-            HashMap::<u32, u32>::with_capacity(count),
-        ),
-        |(mut left, mut right), line| {
-            let mut line = line.split_whitespace();
-            let (first, second) = (line.next().unwrap(), line.next().unwrap());
-            left.push(first.parse().unwrap());
-            *right.entry(second.parse().unwrap()).or_insert(0) += 1;
-            (left, right)
-        },
-    );
-    left.into_iter()
-        .map(|num| num * *right.get(&num).unwrap_or(&0))
-        .sum()
+pub fn part2(input: &str) -> Result<u32, ParseError> {
+    Ok(similarity_score(&parse_lists(input)?)?)
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u32 = 1;
+    type Input = LocationLists;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_lists(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, Overflow> {
+        total_distance(input.clone()).map(Answer::from)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, Overflow> {
+        similarity_score(input).map(Answer::from)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parallelism::{with_config, Config};
+    use proptest::prelude::*;
 
     const INPUT: &str = "
     3   4
@@ -75,14 +145,114 @@ mod tests {
     #[test]
     fn test_day_1_part_1() {
         const EXPECTED: u32 = 11;
-        let output = part1(INPUT);
+        let output = part1(INPUT).unwrap();
         assert_eq!(output, EXPECTED);
     }
 
+    #[test]
+    fn test_day_1_malformed_input() {
+        assert_eq!(
+            part1("3   4\n4").unwrap_err(),
+            ParseError::Malformed {
+                line: 2,
+                expected: "two location IDs"
+            }
+        );
+        assert_eq!(
+            part2("3   x").unwrap_err(),
+            ParseError::InvalidNumber {
+                line: 1,
+                text: "x".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn test_day_1_overflow() {
+        let input = format!("0   {0}\n0   {0}\n", u32::MAX);
+        assert_eq!(part1(&input), Err(ParseError::Overflow));
+        let input = format!("{0}   {0}\n{0}   {0}\n", u32::MAX / 3);
+        assert_eq!(part2(&input), Err(ParseError::Overflow));
+        for spec in ["sequential", "parallel"] {
+            let config = Config::parse(spec).unwrap();
+            assert_eq!(
+                with_config(config, || part2(&input)),
+                Err(ParseError::Overflow)
+            );
+        }
+    }
+
     #[test]
     fn test_day_1_part_2() {
         const EXPECTED: u32 = 31;
-        let output = part2(INPUT);
+        let output = part2(INPUT).unwrap();
         assert_eq!(output, EXPECTED);
     }
+
+    #[test]
+    fn test_day_1_crlf_input() {
+        let input = "\u{feff}".to_owned() + &INPUT.replace('\n', "\r\n");
+        assert_eq!(part1(&input).unwrap(), 11);
+        assert_eq!(part2(&input).unwrap(), 31);
+    }
+
+    #[test]
+    fn test_day_1_parallelism() {
+        for spec in ["sequential", "parallel", "2"] {
+            let config = Config::parse(spec).unwrap();
+            assert_eq!(with_config(config.clone(), || part1(INPUT)).unwrap(), 11);
+            assert_eq!(with_config(config, || part2(INPUT)).unwrap(), 31);
+        }
+    }
+
+    fn location_lists() -> impl Strategy<Value = Vec<(u32, u32)>> {
+        prop::collection::vec((0..1_000_u32, 0..1_000_u32), 1..100)
+    }
+
+    fn format_input(pairs: &[(u32, u32)]) -> String {
+        pairs
+            .iter()
+            .map(|(left, right)| format!("{left}   {right}\n"))
+            .collect()
+    }
+
+    // Pairs up the smallest remaining number from each list one at a time.
+    fn naive_part1(pairs: &[(u32, u32)]) -> u32 {
+        let mut left: Vec<u32> = pairs.iter().map(|(left, _)| *left).collect();
+        let mut right: Vec<u32> = pairs.iter().map(|(_, right)| *right).collect();
+        let mut total = 0;
+        while !left.is_empty() {
+            let smallest_left = (0..left.len()).min_by_key(|&i| left[i]).unwrap();
+            let smallest_right = (0..right.len()).min_by_key(|&i| right[i]).unwrap();
+            total += left
+                .swap_remove(smallest_left)
+                .abs_diff(right.swap_remove(smallest_right));
+        }
+        total
+    }
+
+    fn naive_part2(pairs: &[(u32, u32)]) -> u32 {
+        pairs
+            .iter()
+            .map(|(left, _)| left * pairs.iter().filter(|(_, right)| right == left).count() as u32)
+            .sum()
+    }
+
+    proptest! {
+        #[test]
+        fn test_day_1_part_1_matches_naive(pairs in location_lists()) {
+            prop_assert_eq!(part1(&format_input(&pairs)).unwrap(), naive_part1(&pairs));
+        }
+
+        #[test]
+        fn test_day_1_part_1_is_symmetric(pairs in location_lists()) {
+            let swapped: Vec<_> = pairs.iter().map(|(left, right)| (*right, *left)).collect();
+            prop_assert_eq!(part1(&format_input(&pairs)).unwrap(), part1(&format_input(&swapped)).unwrap());
+        }
+
+        #[test]
+        fn test_day_1_part_2_matches_naive(pairs in location_lists()) {
+            prop_assert_eq!(part2(&format_input(&pairs)).unwrap(), naive_part2(&pairs));
+        }
+    }
 }
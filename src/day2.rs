@@ -1,16 +1,30 @@
+use crate::bytes;
+use crate::error::{parse_number, Overflow, ParseError};
+use crate::parallelism::{self, Mode};
+use crate::solution::Solution;
+use crate::trace;
+use crate::Answer;
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
+use rayon::prelude::*;
 
 #[aoc_generator(day2)]
-pub fn input_generator(input: &str) -> Vec<Vec<i8>> {
-    input
-        .trim()
-        .lines()
-        .map(|line| {
-            line.trim()
-                .split_whitespace()
-                .map(|num| num.parse().unwrap())
-                .collect()
+pub fn input_generator(input: &str) -> Result<Vec<Vec<i8>>, ParseError> {
+    trace::span!("generator", day = 2);
+    bytes::lines(input.as_bytes())
+        .enumerate()
+        .map(|(index, line)| {
+            let report = bytes::fields(line)
+                .map(|num| parse_number(num, index + 1))
+                .collect::<Result<Vec<_>, _>>()?;
+            if report.is_empty() {
+                Err(ParseError::Malformed {
+                    line: index + 1,
+                    expected: "at least one level",
+                })
+            } else {
+                Ok(report)
+            }
         })
         .collect()
 }
@@ -20,9 +34,10 @@ fn is_safe(mut input: impl Iterator<Item = i8>) -> bool {
     let mut count = 0;
     let mut prev = input.next().unwrap();
     for next in input {
-        let diff = next - prev;
+        // widen before subtracting as levels at opposite ends of the range would overflow.
+        let diff = i16::from(next) - i16::from(prev);
         let absdiff = diff.abs();
-        if absdiff < 1 || absdiff > 3 {
+        if !(1..=3).contains(&absdiff) {
             return false;
         }
         total_signum += diff.signum();
@@ -32,44 +47,77 @@ fn is_safe(mut input: impl Iterator<Item = i8>) -> bool {
     total_signum.abs() == count
 }
 
+fn is_safe_with_dampener(report: &[i8]) -> bool {
+    if is_safe(report.iter().copied()) {
+        return true;
+    }
+    (0..report.len()).any(|index_to_remove| {
+        is_safe(
+            report
+                .iter()
+                .copied()
+                .enumerate()
+                .filter_map(|(index, element)| {
+                    if index == index_to_remove {
+                        None
+                    } else {
+                        Some(element)
+                    }
+                }),
+        )
+    })
+}
+
 #[aoc(day2, part1)]
 pub fn part1(input: &[Vec<i8>]) -> usize {
-    input
-        .into_iter()
-        .filter(|report| is_safe(report.into_iter().copied()))
-        .count()
+    trace::span!("part", day = 2, part = 1);
+    let report_is_safe = |report: &&Vec<i8>| is_safe(report.iter().copied());
+    parallelism::run(2, 1, Mode::Sequential, |parallel| {
+        if parallel {
+            input.par_iter().filter(report_is_safe).count()
+        } else {
+            input.iter().filter(report_is_safe).count()
+        }
+    })
 }
 
 #[aoc(day2, part2)]
 pub fn part2(input: &[Vec<i8>]) -> usize {
-    input
-        .into_iter()
-        .filter(|report| {
-            if is_safe(report.into_iter().copied()) {
-                return true;
-            }
-            (0..report.len()).any(|index_to_remove| {
-                is_safe(
-                    report
-                        .into_iter()
-                        .copied()
-                        .enumerate()
-                        .filter_map(|(index, element)| {
-                            if index == index_to_remove {
-                                None
-                            } else {
-                                Some(element)
-                            }
-                        }),
-                )
-            })
-        })
-        .count()
+    trace::span!("part", day = 2, part = 2);
+    let report_is_safe = |report: &&Vec<i8>| is_safe_with_dampener(report);
+    parallelism::run(2, 2, Mode::Sequential, |parallel| {
+        if parallel {
+            input.par_iter().filter(report_is_safe).count()
+        } else {
+            input.iter().filter(report_is_safe).count()
+        }
+    })
+}
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: u32 = 2;
+    type Input = Vec<Vec<i8>>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, Overflow> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, Overflow> {
+        Ok(part2(input).into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parallelism::{with_config, Config};
+    use proptest::prelude::*;
 
     const INPUT: &str = "
     7 6 4 2 1
@@ -83,14 +131,99 @@ mod tests {
     #[test]
     fn test_day_2_part_1() {
         const EXPECTED: usize = 2;
-        let output = part1(&input_generator(INPUT));
+        let output = part1(&input_generator(INPUT).unwrap());
         assert_eq!(output, EXPECTED);
     }
 
+    #[test]
+    fn test_day_2_malformed_input() {
+        assert!(matches!(
+            input_generator("1 2\n\n3 4"),
+            Err(ParseError::Malformed { line: 2, .. })
+        ));
+        assert!(matches!(
+            input_generator("1 2 300"),
+            Err(ParseError::InvalidNumber { line: 1, .. })
+        ));
+    }
+
     #[test]
     fn test_day_2_part_2() {
         const EXPECTED: usize = 4;
-        let output = part2(&input_generator(INPUT));
+        let output = part2(&input_generator(INPUT).unwrap());
         assert_eq!(output, EXPECTED);
     }
+
+    #[test]
+    fn test_day_2_crlf_input() {
+        let input = "\u{feff}".to_owned() + &INPUT.replace('\n', "\r\n");
+        assert_eq!(part1(&input_generator(&input).unwrap()), 2);
+        assert_eq!(part2(&input_generator(&input).unwrap()), 4);
+    }
+
+    #[test]
+    fn test_day_2_parallelism() {
+        let input = input_generator(INPUT).unwrap();
+        for spec in ["sequential", "parallel", "2"] {
+            let config = Config::parse(spec).unwrap();
+            assert_eq!(with_config(config.clone(), || part1(&input)), 2);
+            assert_eq!(with_config(config, || part2(&input)), 4);
+        }
+    }
+
+    // Small levels close together so that a decent share of reports come out safe.
+    fn reports() -> impl Strategy<Value = Vec<Vec<i8>>> {
+        prop::collection::vec(prop::collection::vec(0..12_i8, 1..8), 1..50)
+    }
+
+    fn format_input(reports: &[Vec<i8>]) -> String {
+        reports
+            .iter()
+            .map(|report| {
+                let levels: Vec<String> = report.iter().map(|level| level.to_string()).collect();
+                levels.join(" ") + "\n"
+            })
+            .collect()
+    }
+
+    fn naive_is_safe(report: &[i8]) -> bool {
+        let differences: Vec<i8> = report.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        differences
+            .iter()
+            .all(|difference| (1..=3).contains(difference))
+            || differences
+                .iter()
+                .all(|difference| (-3..=-1).contains(difference))
+    }
+
+    fn naive_is_safe_with_dampener(report: &[i8]) -> bool {
+        (0..report.len()).any(|index| {
+            let mut removed = report.to_vec();
+            removed.remove(index);
+            naive_is_safe(&removed)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_day_2_part_1_matches_naive(reports in reports()) {
+            let expected = reports.iter().filter(|report| naive_is_safe(report)).count();
+            prop_assert_eq!(part1(&input_generator(&format_input(&reports)).unwrap()), expected);
+        }
+
+        #[test]
+        fn test_day_2_part_2_matches_naive(reports in reports()) {
+            let expected = reports
+                .iter()
+                .filter(|report| naive_is_safe(report) || naive_is_safe_with_dampener(report))
+                .count();
+            prop_assert_eq!(part2(&input_generator(&format_input(&reports)).unwrap()), expected);
+        }
+
+        #[test]
+        fn test_day_2_part_1_never_exceeds_part_2(reports in reports()) {
+            let input = input_generator(&format_input(&reports)).unwrap();
+            prop_assert!(part1(&input) <= part2(&input));
+        }
+    }
 }
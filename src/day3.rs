@@ -1,3 +1,9 @@
+use crate::bytes;
+use crate::conformance;
+use crate::error::{Overflow, ParseError};
+use crate::solution::Solution;
+use crate::trace;
+use crate::Answer;
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 use nom::branch::alt;
@@ -7,6 +13,7 @@ use nom::combinator::{map, map_res};
 use nom::multi::{many0, many_till};
 use nom::sequence::{delimited, separated_pair};
 use nom::IResult;
+use std::fmt;
 
 type IntegerType = u32;
 
@@ -17,61 +24,299 @@ pub enum Command {
     Disable,
 }
 
-fn parse_integer(input: &str) -> IResult<&str, IntegerType> {
-    map_res(digit1, |digit_str: &str| digit_str.parse())(input)
+// The puzzle only allows up to three digits, but any number that fits is accepted unless
+// the input has to conform.
+fn parse_integer<const MAX_DIGITS: usize>(input: &[u8]) -> IResult<&[u8], IntegerType> {
+    map_res(digit1, |digits: &[u8]| {
+        if digits.len() > MAX_DIGITS {
+            return Err(());
+        }
+        bytes::parse(digits).ok_or(())
+    })(input)
 }
 
-fn parse_mul(input: &str) -> IResult<&str, Command> {
+fn parse_mul<const MAX_DIGITS: usize>(input: &[u8]) -> IResult<&[u8], Command> {
     let (input, (left, right)) = delimited(
-        tag("mul("),
-        separated_pair(parse_integer, char(','), parse_integer),
+        tag(b"mul("),
+        separated_pair(
+            parse_integer::<MAX_DIGITS>,
+            char(','),
+            parse_integer::<MAX_DIGITS>,
+        ),
         char(')'),
     )(input)?;
     Ok((input, Command::Mul(left, right)))
 }
 
-fn parse_enable(input: &str) -> IResult<&str, Command> {
-    let (input, _) = tag("do()")(input)?;
+fn parse_enable(input: &[u8]) -> IResult<&[u8], Command> {
+    let (input, _) = tag(b"do()")(input)?;
     Ok((input, Command::Enable))
 }
 
-fn parse_disable(input: &str) -> IResult<&str, Command> {
-    let (input, _) = tag("don't()")(input)?;
+fn parse_disable(input: &[u8]) -> IResult<&[u8], Command> {
+    let (input, _) = tag(b"don't()")(input)?;
     Ok((input, Command::Disable))
 }
 
-fn parse_command(input: &str) -> IResult<&str, Command> {
-    alt((parse_mul, parse_enable, parse_disable))(input)
+fn parse_command<const MAX_DIGITS: usize>(input: &[u8]) -> IResult<&[u8], Command> {
+    alt((parse_mul::<MAX_DIGITS>, parse_enable, parse_disable))(input)
 }
 
-fn parse_command_with_prefix(input: &str) -> IResult<&str, Command> {
-    map(many_till(take(1u8), parse_command), |(_, matched)| matched)(input)
+fn parse_command_with_prefix<const MAX_DIGITS: usize>(input: &[u8]) -> IResult<&[u8], Command> {
+    map(
+        many_till(take(1u8), parse_command::<MAX_DIGITS>),
+        |(_, matched)| matched,
+    )(input)
 }
 
-fn parse_all_commands(input: &str) -> IResult<&str, Vec<Command>> {
-    many0(parse_command_with_prefix)(input)
+fn parse_all_commands<const MAX_DIGITS: usize>(input: &[u8]) -> IResult<&[u8], Vec<Command>> {
+    many0(parse_command_with_prefix::<MAX_DIGITS>)(input)
 }
 
 #[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> Vec<Command> {
-    let (_, results) = parse_all_commands(input).unwrap();
-    results
+pub fn input_generator(input: &str) -> Result<Vec<Command>, ParseError> {
+    trace::span!("generator", day = 3);
+    // corrupted instructions are part of the puzzle, so a mul with longer numbers is just
+    // another one of those rather than an error unless the input has to conform.
+    let parsed = if conformance::is_strict() {
+        check_digits(input)?;
+        parse_all_commands::<3>(bytes::trim(input.as_bytes()))
+    } else {
+        parse_all_commands::<{ usize::MAX }>(bytes::trim(input.as_bytes()))
+    };
+    // many0 stops at the first thing it can't parse rather than failing, so this is only
+    // here in case that changes.
+    let (_, commands) = parsed.map_err(|_| ParseError::Malformed {
+        line: 1,
+        expected: "instructions",
+    })?;
+    Ok(commands)
 }
 
-#[aoc(day3, part1)]
-pub fn part1(input: &[Command]) -> IntegerType {
-    input.into_iter().fold(0, |mut state, command| {
-        match command {
-            Command::Mul(first, second) => state += first * second,
-            _ => {}
+// The puzzle says the numbers in a mul have one to three digits.
+fn check_digits(input: &str) -> Result<(), ParseError> {
+    let input = input
+        .strip_prefix('\u{feff}')
+        .unwrap_or(input)
+        .trim_ascii_start();
+    let too_long = near_misses(input).into_iter().find(|near_miss| {
+        matches!(near_miss.reason, Reason::TooManyDigits(_))
+            && parse_mul::<{ usize::MAX }>(&input.as_bytes()[near_miss.position..]).is_ok()
+    });
+    match too_long {
+        Some(near_miss) => Err(ParseError::Nonconforming {
+            line: Some(near_miss.line),
+            reason: format!("column {}: {}", near_miss.column, near_miss.reason),
+        }),
+        None => Ok(()),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Instruction {
+    Mul,
+    Do,
+    Dont,
+}
+
+impl Instruction {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Mul => "mul",
+            Self::Do => "do",
+            Self::Dont => "don't",
+        }
+    }
+
+    // `don't` is checked before `do`, since it starts with it.
+    fn starting(input: &[u8]) -> Option<Self> {
+        [Self::Mul, Self::Dont, Self::Do]
+            .into_iter()
+            .find(|instruction| input.starts_with(instruction.name().as_bytes()))
+    }
+}
+
+/// Why something that starts like an instruction isn't one. Arguments are numbered from 1.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Reason {
+    MissingOpeningParenthesis,
+    MissingArgument(usize),
+    /// More than three digits, which only matters when the input has to conform.
+    TooManyDigits(usize),
+    TooLarge(usize),
+    MissingComma,
+    MissingClosingParenthesis,
+    /// The input ends part way through the instruction.
+    Truncated,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingOpeningParenthesis => write!(f, "the name isn't followed by '('"),
+            Self::MissingArgument(argument) => write!(f, "argument {argument} has no digits"),
+            Self::TooManyDigits(argument) => {
+                write!(f, "argument {argument} has more than three digits")
+            }
+            Self::TooLarge(argument) => {
+                write!(f, "argument {argument} is larger than {}", IntegerType::MAX)
+            }
+            Self::MissingComma => write!(f, "the arguments aren't separated by ','"),
+            Self::MissingClosingParenthesis => write!(f, "the arguments aren't followed by ')'"),
+            Self::Truncated => write!(f, "the input ends before the instruction does"),
+        }
+    }
+}
+
+/// Text that starts like an instruction but isn't one, which the generator skips over.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NearMiss {
+    /// The byte offset into the input.
+    pub position: usize,
+    pub line: usize,
+    /// In bytes from the start of the line, counting from 1.
+    pub column: usize,
+    pub instruction: Instruction,
+    /// From the start of the name up to and including the character that's wrong.
+    pub text: String,
+    pub reason: Reason,
+}
+
+impl fmt::Display for NearMiss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {:?}: {}",
+            self.line, self.column, self.text, self.reason
+        )
+    }
+}
+
+// Moves past `expected`, or gives the reason and the length of the text up to and
+// including whatever is there instead.
+fn expect_byte(
+    input: &[u8],
+    index: &mut usize,
+    expected: u8,
+    reason: Reason,
+) -> Result<(), (Reason, usize)> {
+    match input.get(*index) {
+        Some(&byte) if byte == expected => {
+            *index += 1;
+            Ok(())
+        }
+        Some(_) => Err((reason, *index + 1)),
+        None => Err((Reason::Truncated, *index)),
+    }
+}
+
+fn expect_integer(
+    input: &[u8],
+    index: &mut usize,
+    argument: usize,
+    max_digits: usize,
+) -> Result<(), (Reason, usize)> {
+    let start = *index;
+    let end = start
+        + input[start..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+    if end == start {
+        return Err(match input.get(end) {
+            Some(_) => (Reason::MissingArgument(argument), end + 1),
+            None => (Reason::Truncated, end),
+        });
+    }
+    if end - start > max_digits {
+        Err((Reason::TooManyDigits(argument), end))
+    } else if bytes::parse::<IntegerType>(&input[start..end]).is_none() {
+        Err((Reason::TooLarge(argument), end))
+    } else {
+        *index = end;
+        Ok(())
+    }
+}
+
+// The same rules as `parse_command`, but saying where and why it fails.
+fn diagnose(
+    instruction: Instruction,
+    input: &[u8],
+    max_digits: usize,
+) -> Result<(), (Reason, usize)> {
+    let mut index = instruction.name().len();
+    expect_byte(input, &mut index, b'(', Reason::MissingOpeningParenthesis)?;
+    if instruction == Instruction::Mul {
+        expect_integer(input, &mut index, 1, max_digits)?;
+        expect_byte(input, &mut index, b',', Reason::MissingComma)?;
+        expect_integer(input, &mut index, 2, max_digits)?;
+    }
+    expect_byte(input, &mut index, b')', Reason::MissingClosingParenthesis)
+}
+
+/// Every place in the input where an instruction's name starts but the generator couldn't
+/// parse an instruction, following the same conformance as the generator does.
+pub fn near_misses(input: &str) -> Vec<NearMiss> {
+    let max_digits = if conformance::is_strict() {
+        3
+    } else {
+        usize::MAX
+    };
+    let bytes = input.as_bytes();
+    let mut near_misses = Vec::new();
+    let mut line = 1;
+    let mut line_start = 0;
+    for position in 0..bytes.len() {
+        if bytes[position] == b'\n' {
+            line += 1;
+            line_start = position + 1;
+            continue;
+        }
+        let rest = &bytes[position..];
+        let Some(instruction) = Instruction::starting(rest) else {
+            continue;
         };
-        state
+        if let Err((reason, length)) = diagnose(instruction, rest, max_digits) {
+            let mut end = position + length;
+            while !input.is_char_boundary(end) {
+                end += 1;
+            }
+            near_misses.push(NearMiss {
+                position,
+                line,
+                column: position - line_start + 1,
+                instruction,
+                text: input[position..end].to_owned(),
+                reason,
+            });
+        }
+    }
+    near_misses
+}
+
+fn add_product(
+    total: IntegerType,
+    left: IntegerType,
+    right: IntegerType,
+) -> Result<IntegerType, Overflow> {
+    left.checked_mul(right)
+        .and_then(|product| total.checked_add(product))
+        .ok_or(Overflow)
+}
+
+#[aoc(day3, part1)]
+pub fn part1(input: &[Command]) -> Result<IntegerType, Overflow> {
+    trace::span!("part", day = 3, part = 1);
+    input.iter().try_fold(0, |total, command| match command {
+        Command::Mul(first, second) => add_product(total, *first, *second),
+        _ => Ok(total),
     })
 }
 
 #[aoc(day3, part2)]
-pub fn part2(input: &[Command]) -> IntegerType {
-    let mut total = 0;
+pub fn part2(input: &[Command]) -> Result<IntegerType, Overflow> {
+    trace::span!("part", day = 3, part = 2);
+    let mut total: IntegerType = 0;
     let mut enabled = true;
     for command in input {
         match command {
@@ -79,24 +324,45 @@ pub fn part2(input: &[Command]) -> IntegerType {
             Command::Disable => enabled = false,
             Command::Mul(left, right) => {
                 if enabled {
-                    total += left * right
+                    total = add_product(total, *left, *right)?;
                 }
             }
         }
     }
-    total
+    Ok(total)
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u32 = 3;
+    type Input = Vec<Command>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, Overflow> {
+        part1(input).map(Answer::from)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, Overflow> {
+        part2(input).map(Answer::from)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::conformance::{with_conformance, Conformance};
+    use proptest::prelude::*;
 
     #[test]
     fn test_day_3_part_1() {
         const INPUT: &str =
             "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
         const EXPECTED: IntegerType = 161;
-        let output = part1(&input_generator(INPUT));
+        let output = part1(&input_generator(INPUT).unwrap()).unwrap();
         assert_eq!(output, EXPECTED);
     }
 
@@ -105,7 +371,158 @@ mod tests {
         const INPUT: &str =
             "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
         const EXPECTED: IntegerType = 48;
-        let output = part2(&input_generator(INPUT));
+        let output = part2(&input_generator(INPUT).unwrap()).unwrap();
         assert_eq!(output, EXPECTED);
     }
+
+    #[test]
+    fn test_day_3_crlf_input() {
+        const INPUT: &str =
+            "\u{feff}\r\n    xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64]\r\n    (mul(11,8)undo()?mul(8,5))\r\n";
+        assert_eq!(part1(&input_generator(INPUT).unwrap()).unwrap(), 161);
+        assert_eq!(part2(&input_generator(INPUT).unwrap()).unwrap(), 48);
+    }
+
+    #[test]
+    fn test_day_3_strict_input() {
+        const INPUT: &str = "mul(2,4)mul(1234,5)mul(999,1)";
+        assert_eq!(
+            part1(&input_generator(INPUT).unwrap()).unwrap(),
+            8 + 6170 + 999
+        );
+        let strict = |input| with_conformance(Conformance::Strict, || input_generator(input));
+        assert_eq!(
+            strict(INPUT).unwrap_err(),
+            ParseError::Nonconforming {
+                line: Some(1),
+                reason: "column 9: argument 1 has more than three digits".to_owned()
+            }
+        );
+        assert_eq!(
+            strict("\n  mul(2,4)\n  xmul(1,5678)")
+                .unwrap_err()
+                .to_string(),
+            "line 2: column 4: argument 2 has more than three digits"
+        );
+        // a mul that would be skipped anyway is just corrupted, however long its numbers.
+        let input = strict("mul(2,4)mul(1234,5]mul(999,1)").unwrap();
+        assert_eq!(part1(&input).unwrap(), 8 + 999);
+    }
+
+    #[test]
+    fn test_day_3_overflow() {
+        let input = input_generator("mul(65536,65536)").unwrap();
+        assert_eq!(part1(&input), Err(Overflow));
+        let input = input_generator("mul(65535,65536)don't()mul(65535,65536)").unwrap();
+        assert_eq!(part1(&input), Err(Overflow));
+        assert_eq!(part2(&input), Ok(65535 * 65536));
+    }
+
+    #[test]
+    fn test_day_3_near_misses() {
+        const INPUT: &str = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)\n\
+                             mul ( 2 , 4 )don't(x)mul(,1)mul(1 2)mul(1234,5)mul(4294967296,1)mul(8";
+        let near_misses = with_conformance(Conformance::Lenient, || near_misses(INPUT));
+        let found: Vec<_> = near_misses
+            .iter()
+            .map(|near_miss| (near_miss.text.as_str(), near_miss.reason))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("mul[", Reason::MissingOpeningParenthesis),
+                ("do_", Reason::MissingOpeningParenthesis),
+                ("mul(32,64]", Reason::MissingClosingParenthesis),
+                ("mul ", Reason::MissingOpeningParenthesis),
+                ("don't(x", Reason::MissingClosingParenthesis),
+                ("mul(,", Reason::MissingArgument(1)),
+                ("mul(1 ", Reason::MissingComma),
+                ("mul(4294967296", Reason::TooLarge(1)),
+                ("mul(8", Reason::Truncated),
+            ]
+        );
+        assert_eq!(near_misses[1].instruction, Instruction::Do);
+        assert_eq!(
+            near_misses[3].to_string(),
+            "line 2, column 1: \"mul \": the name isn't followed by '('"
+        );
+
+        let strict = with_conformance(Conformance::Strict, || super::near_misses(INPUT));
+        assert_eq!(strict[7].text, "mul(1234");
+        assert_eq!(strict[7].reason, Reason::TooManyDigits(1));
+    }
+
+    // Valid instructions mixed with fragments of them, but never digits outside of a
+    // generated `mul` so the products can't overflow.
+    fn corrupted_memory() -> impl Strategy<Value = String> {
+        let segment = prop_oneof![
+            (0..1000_u32, 0..1000_u32).prop_map(|(left, right)| format!("mul({left},{right})")),
+            Just("do()".to_owned()),
+            Just("don't()".to_owned()),
+            "[mul(),don't\\[\\]x! ]{0,6}",
+        ];
+        prop::collection::vec(segment, 0..40).prop_map(|segments| segments.concat())
+    }
+
+    fn naive_parse_mul(input: &str) -> Option<(IntegerType, IntegerType)> {
+        let rest = input.strip_prefix("mul(")?;
+        let (left, rest) = rest.split_at(rest.find(|c: char| !c.is_ascii_digit())?);
+        let rest = rest.strip_prefix(',')?;
+        let (right, rest) = rest.split_at(rest.find(|c: char| !c.is_ascii_digit())?);
+        rest.strip_prefix(')')?;
+        Some((left.parse().ok()?, right.parse().ok()?))
+    }
+
+    // Tries every instruction at every position rather than parsing.
+    fn naive_run(input: &str, use_conditionals: bool) -> IntegerType {
+        let mut total = 0;
+        let mut enabled = true;
+        for index in 0..input.len() {
+            let rest = &input[index..];
+            if rest.starts_with("do()") {
+                enabled = true;
+            } else if rest.starts_with("don't()") {
+                enabled = false;
+            } else if let Some((left, right)) = naive_parse_mul(rest) {
+                if enabled || !use_conditionals {
+                    total += left * right;
+                }
+            }
+        }
+        total
+    }
+
+    proptest! {
+        #[test]
+        fn test_day_3_part_1_matches_naive(memory in corrupted_memory()) {
+            prop_assert_eq!(part1(&input_generator(&memory).unwrap()).unwrap(), naive_run(&memory, false));
+        }
+
+        #[test]
+        fn test_day_3_part_2_matches_naive(memory in corrupted_memory()) {
+            prop_assert_eq!(part2(&input_generator(&memory).unwrap()).unwrap(), naive_run(&memory, true));
+        }
+
+        #[test]
+        fn test_day_3_near_misses_are_what_the_parser_skips(memory in corrupted_memory()) {
+            let positions: Vec<_> = with_conformance(Conformance::Lenient, || near_misses(&memory))
+                .iter()
+                .map(|near_miss| near_miss.position)
+                .collect();
+            let skipped: Vec<_> = (0..memory.len())
+                .filter(|&position| {
+                    let rest = &memory.as_bytes()[position..];
+                    Instruction::starting(rest).is_some()
+                        && parse_command::<{ usize::MAX }>(rest).is_err()
+                })
+                .collect();
+            prop_assert_eq!(positions, skipped);
+        }
+
+        #[test]
+        fn test_day_3_part_2_never_exceeds_part_1(memory in corrupted_memory()) {
+            let commands = input_generator(&memory).unwrap();
+            prop_assert!(part2(&commands).unwrap() <= part1(&commands).unwrap());
+        }
+    }
 }
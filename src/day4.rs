@@ -1,3 +1,9 @@
+use crate::bytes;
+use crate::conformance;
+use crate::error::{Overflow, ParseError};
+use crate::solution::Solution;
+use crate::trace;
+use crate::Answer;
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 
@@ -7,26 +13,33 @@ use aoc_runner_derive::aoc_generator;
 const MAX_GRID_LENGTH: usize = 19_600;
 
 #[derive(Debug)]
-struct WordSearch {
-    storage: [char; MAX_GRID_LENGTH],
+pub struct WordSearch {
+    storage: [u8; MAX_GRID_LENGTH],
     number_of_columns: usize,
     number_of_rows: usize,
 }
 
 #[aoc_generator(day4)]
-fn input_generator(input: &str) -> WordSearch {
-    let mut storage = ['\0'; MAX_GRID_LENGTH];
+pub fn input_generator(input: &str) -> Result<WordSearch, ParseError> {
+    trace::span!("generator", day = 4);
+    if conformance::is_strict() {
+        conformance::check_grid(input.as_bytes(), b"XMAS")?;
+    }
+    let mut storage = [0; MAX_GRID_LENGTH];
     let mut index = 0;
     let mut number_of_columns = 0;
     let mut number_of_rows = 1;
-    let mut lines = input.trim().lines().map(|line| line.trim());
+    let mut lines = bytes::lines(input.as_bytes());
+    let too_large = ParseError::TooLarge {
+        limit: MAX_GRID_LENGTH,
+    };
 
     // do first line as a special case so it can also set number_of_columns.
     // don't need to worry about that after first line as all rows have same number of columns.
     {
-        for character in lines.next().unwrap().chars() {
+        for &character in lines.next().ok_or(ParseError::Empty)? {
             number_of_columns += 1;
-            storage[index] = character;
+            *storage.get_mut(index).ok_or(too_large.clone())? = character;
             index += 1;
         }
     }
@@ -34,17 +47,23 @@ fn input_generator(input: &str) -> WordSearch {
     // process rest of the lines in a similar fashion, but noneed to update number_of_columns.
     for line in lines {
         number_of_rows += 1;
-        for character in line.chars() {
-            storage[index] = character;
+        for &character in line {
+            *storage.get_mut(index).ok_or(too_large.clone())? = character;
             index += 1;
         }
     }
 
-    WordSearch {
+    // the solvers index by row and column, so the whole rectangle needs to fit even if
+    // some rows were shorter than the first.
+    if number_of_rows * number_of_columns > MAX_GRID_LENGTH {
+        return Err(too_large);
+    }
+
+    Ok(WordSearch {
         storage,
         number_of_columns,
         number_of_rows,
-    }
+    })
 }
 
 fn check_bounds(row_index: isize, column_index: isize, input: &WordSearch) -> bool {
@@ -64,7 +83,7 @@ fn has_mas_from_location_with_update_func(
     if !check_bounds(row_index, column_index, input) {
         return false;
     }
-    if input.storage[row_index as usize * input.number_of_columns + column_index as usize] != 'M' {
+    if input.storage[row_index as usize * input.number_of_columns + column_index as usize] != b'M' {
         return false;
     }
 
@@ -73,7 +92,7 @@ fn has_mas_from_location_with_update_func(
     if !check_bounds(row_index, column_index, input) {
         return false;
     }
-    if input.storage[row_index as usize * input.number_of_columns + column_index as usize] != 'A' {
+    if input.storage[row_index as usize * input.number_of_columns + column_index as usize] != b'A' {
         return false;
     }
 
@@ -82,11 +101,11 @@ fn has_mas_from_location_with_update_func(
     if !check_bounds(row_index, column_index, input) {
         return false;
     }
-    input.storage[row_index as usize * input.number_of_columns + column_index as usize] == 'S'
+    input.storage[row_index as usize * input.number_of_columns + column_index as usize] == b'S'
 }
 
 fn count_xmas_starting_from(input: &WordSearch, row_index: usize, column_index: usize) -> u32 {
-    if input.storage[row_index * input.number_of_columns + column_index] == 'X' {
+    if input.storage[row_index * input.number_of_columns + column_index] == b'X' {
         let mut total = 0;
         let (row_index, column_index) = (row_index as isize, column_index as isize);
 
@@ -177,7 +196,7 @@ fn count_xmas_starting_from(input: &WordSearch, row_index: usize, column_index:
 }
 
 fn count_x_mas_centered_on(input: &WordSearch, row_index: usize, column_index: usize) -> u32 {
-    if input.storage[row_index * input.number_of_columns + column_index] == 'A' {
+    if input.storage[row_index * input.number_of_columns + column_index] == b'A' {
         let (row_index, column_index) = (row_index as isize, column_index as isize);
         ((has_mas_from_location_with_update_func(
             input,
@@ -206,7 +225,8 @@ fn count_x_mas_centered_on(input: &WordSearch, row_index: usize, column_index: u
 }
 
 #[aoc(day4, part1)]
-fn part1(input: &WordSearch) -> u32 {
+pub fn part1(input: &WordSearch) -> u32 {
+    trace::span!("part", day = 4, part = 1);
     let mut total_words = 0;
     for row_index in 0..input.number_of_rows {
         for column_index in 0..input.number_of_columns {
@@ -217,7 +237,8 @@ fn part1(input: &WordSearch) -> u32 {
 }
 
 #[aoc(day4, part2)]
-fn part2(input: &WordSearch) -> u32 {
+pub fn part2(input: &WordSearch) -> u32 {
+    trace::span!("part", day = 4, part = 2);
     let mut total_words = 0;
     for row_index in 0..input.number_of_rows {
         for column_index in 0..input.number_of_columns {
@@ -227,9 +248,30 @@ fn part2(input: &WordSearch) -> u32 {
     total_words
 }
 
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: u32 = 4;
+    type Input = WordSearch;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, Overflow> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, Overflow> {
+        Ok(part2(input).into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::conformance::{with_conformance, Conformance};
+    use proptest::prelude::*;
 
     const INPUT: &str = "
 		MMMSXXMASM
@@ -247,14 +289,132 @@ mod tests {
     #[test]
     fn test_day_4_part_1() {
         const EXPECTED: u32 = 18;
-        let output = part1(&input_generator(INPUT));
+        let output = part1(&input_generator(INPUT).unwrap());
         assert_eq!(output, EXPECTED);
     }
 
+    #[test]
+    fn test_day_4_malformed_input() {
+        assert!(matches!(input_generator("  \n "), Err(ParseError::Empty)));
+        let too_wide = "X".repeat(MAX_GRID_LENGTH) + "\nX";
+        assert!(matches!(
+            input_generator(&too_wide),
+            Err(ParseError::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_day_4_strict_input() {
+        let strict = |input| with_conformance(Conformance::Strict, || input_generator(input));
+        assert!(strict(INPUT).is_ok());
+        assert!(input_generator("XMAS\nXMA\nXMAS").is_ok());
+        assert_eq!(
+            strict("XMAS\nXMA\nXMAS").unwrap_err(),
+            ParseError::Nonconforming {
+                line: Some(2),
+                reason: "row is 3 wide, but the first row is 4".to_owned()
+            }
+        );
+        assert!(matches!(
+            strict("XMAS\nXM.S"),
+            Err(ParseError::Nonconforming { line: Some(2), .. })
+        ));
+    }
+
     #[test]
     fn test_day_4_part_2() {
         const EXPECTED: u32 = 9;
-        let output = part2(&input_generator(INPUT));
+        let output = part2(&input_generator(INPUT).unwrap());
         assert_eq!(output, EXPECTED);
     }
+
+    #[test]
+    fn test_day_4_crlf_input() {
+        let input = "\u{feff}".to_owned() + &INPUT.replace('\n', "\r\n");
+        assert_eq!(part1(&input_generator(&input).unwrap()), 18);
+        assert_eq!(part2(&input_generator(&input).unwrap()), 9);
+    }
+
+    fn word_grids() -> impl Strategy<Value = Vec<Vec<char>>> {
+        (1..12_usize, 1..12_usize).prop_flat_map(|(rows, columns)| {
+            prop::collection::vec(
+                prop::collection::vec(prop::sample::select(vec!['X', 'M', 'A', 'S']), columns),
+                rows,
+            )
+        })
+    }
+
+    fn format_input(grid: &[Vec<char>]) -> String {
+        grid.iter()
+            .map(|row| row.iter().collect::<String>() + "\n")
+            .collect()
+    }
+
+    fn naive_word_at(
+        grid: &[Vec<char>],
+        word: &str,
+        row: isize,
+        column: isize,
+        step: (isize, isize),
+    ) -> bool {
+        word.chars().enumerate().all(|(offset, expected)| {
+            let (row, column) = (
+                row + step.0 * offset as isize,
+                column + step.1 * offset as isize,
+            );
+            row >= 0
+                && column >= 0
+                && grid
+                    .get(row as usize)
+                    .and_then(|row| row.get(column as usize))
+                    == Some(&expected)
+        })
+    }
+
+    fn naive_part1(grid: &[Vec<char>]) -> u32 {
+        let mut total = 0;
+        for row in 0..grid.len() as isize {
+            for column in 0..grid[0].len() as isize {
+                for step in [
+                    (-1, -1),
+                    (-1, 0),
+                    (-1, 1),
+                    (0, -1),
+                    (0, 1),
+                    (1, -1),
+                    (1, 0),
+                    (1, 1),
+                ] {
+                    total += naive_word_at(grid, "XMAS", row, column, step) as u32;
+                }
+            }
+        }
+        total
+    }
+
+    fn naive_part2(grid: &[Vec<char>]) -> u32 {
+        let mut total = 0;
+        for row in 1..grid.len() as isize - 1 {
+            for column in 1..grid[0].len() as isize - 1 {
+                let diagonal = |word| naive_word_at(grid, word, row - 1, column - 1, (1, 1));
+                let anti_diagonal = |word| naive_word_at(grid, word, row - 1, column + 1, (1, -1));
+                total += ((diagonal("MAS") || diagonal("SAM"))
+                    && (anti_diagonal("MAS") || anti_diagonal("SAM")))
+                    as u32;
+            }
+        }
+        total
+    }
+
+    proptest! {
+        #[test]
+        fn test_day_4_part_1_matches_naive(grid in word_grids()) {
+            prop_assert_eq!(part1(&input_generator(&format_input(&grid)).unwrap()), naive_part1(&grid));
+        }
+
+        #[test]
+        fn test_day_4_part_2_matches_naive(grid in word_grids()) {
+            prop_assert_eq!(part2(&input_generator(&format_input(&grid)).unwrap()), naive_part2(&grid));
+        }
+    }
 }
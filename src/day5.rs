@@ -1,100 +1,575 @@
+use crate::bytes;
+use crate::conformance;
+use crate::error::{parse_number, Overflow, ParseError};
+use crate::parallelism::{self, Mode};
+use crate::solution::Solution;
+use crate::trace;
+use crate::Answer;
 use aoc_runner_derive::aoc;
 use core::cmp::Ordering;
-use std::collections::HashMap;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::iter::FusedIterator;
 
 type IntegerType = u32;
+pub type Update = Vec<IntegerType>;
 
-fn parse_page_ordering_rules(input: &str) -> HashMap<(&str, &str), bool> {
-    let mut hm = HashMap::new();
-    for line in input.lines() {
-        let (first, second) = line.trim().split_once("|").unwrap();
-        hm.insert((first, second), true);
-        hm.insert((second, first), false);
+/// A page ordering rule that an update breaks, by having `after` before `before`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Violation {
+    pub before: IntegerType,
+    pub after: IntegerType,
+}
+
+/// Page ordering rules that can be added and retracted one at a time, for checking
+/// updates against whatever rules are in force at the time.
+#[derive(Clone, Debug, Default)]
+pub struct RuleBook {
+    rules: HashSet<(IntegerType, IntegerType)>,
+    // the pages that must come before each page, for answering queries about it.
+    predecessors: HashMap<IntegerType, BTreeSet<IntegerType>>,
+}
+
+impl RuleBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the rule `before|after`, returning whether it's new.
+    pub fn add_rule(&mut self, before: IntegerType, after: IntegerType) -> bool {
+        self.predecessors.entry(after).or_default().insert(before);
+        self.rules.insert((before, after))
+    }
+
+    /// Retracts the rule `before|after`, returning whether it was there.
+    pub fn retract_rule(&mut self, before: IntegerType, after: IntegerType) -> bool {
+        if let Some(predecessors) = self.predecessors.get_mut(&after) {
+            predecessors.remove(&before);
+            if predecessors.is_empty() {
+                self.predecessors.remove(&after);
+            }
+        }
+        self.rules.remove(&(before, after))
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether there's a rule that `before` must come before `after`.
+    pub fn requires(&self, before: IntegerType, after: IntegerType) -> bool {
+        self.rules.contains(&(before, after))
+    }
+
+    /// The pages with a rule saying they must come before `page`, in ascending order.
+    pub fn must_come_before(&self, page: IntegerType) -> impl Iterator<Item = IntegerType> + '_ {
+        self.predecessors.get(&page).into_iter().flatten().copied()
+    }
+
+    /// Checks an update against the rules, returning the first one it breaks.
+    pub fn check(&self, update: &[IntegerType]) -> Result<(), Violation> {
+        for (index, &page) in update.iter().enumerate() {
+            for &later in &update[index + 1..] {
+                if self.requires(later, page) {
+                    return Err(Violation {
+                        before: later,
+                        after: page,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The rules in order, only between pages of `update` if there is one.
+    fn sorted_rules(&self, update: Option<&[IntegerType]>) -> Vec<(IntegerType, IntegerType)> {
+        let mut rules: Vec<_> = self
+            .rules
+            .iter()
+            .copied()
+            .filter(|(before, after)| {
+                update.is_none_or(|update| update.contains(before) && update.contains(after))
+            })
+            .collect();
+        rules.sort_unstable();
+        rules
+    }
+
+    /// The rules as a Graphviz digraph, with an edge from each page to each page that must
+    /// come after it. Given an update, only the rules between its pages are included and
+    /// the ones it breaks are drawn in red.
+    pub fn to_dot(&self, update: Option<&[IntegerType]>) -> String {
+        let mut dot = "digraph rules {\n".to_owned();
+        if let Some(update) = update {
+            for page in update {
+                dot += &format!("    {page};\n");
+            }
+        }
+        for (before, after) in self.sorted_rules(update) {
+            let position = |page| update?.iter().position(|other| *other == page);
+            if position(before) > position(after) {
+                dot += &format!("    {before} -> {after} [color=red];\n");
+            } else {
+                dot += &format!("    {before} -> {after};\n");
+            }
+        }
+        dot + "}\n"
+    }
+
+    /// The rules as a JSON object with a list of the pages that must come after each page.
+    /// Given an update, only the rules between its pages are included, and all of its pages
+    /// are listed even if there are none after them.
+    pub fn to_json(&self, update: Option<&[IntegerType]>) -> String {
+        let mut successors: BTreeMap<IntegerType, Vec<IntegerType>> = update
+            .into_iter()
+            .flatten()
+            .map(|page| (*page, Vec::new()))
+            .collect();
+        for (before, after) in self.sorted_rules(update) {
+            successors.entry(before).or_default().push(after);
+        }
+        serde_json::to_string(&successors).unwrap()
+    }
+
+    /// Reads lines from `reader` as they arrive, adding rules like `47|53`, retracting rules
+    /// like `-47|53`, and checking updates like `75,47,61` against the rules read so far.
+    /// Blank lines are skipped, and line numbers in errors count from 1 at the first line.
+    pub fn process<R: BufRead>(&mut self, reader: R) -> Checks<'_, R> {
+        Checks {
+            rule_book: self,
+            reader,
+            line: Vec::new(),
+            line_number: 0,
+            finished: false,
+        }
     }
-    hm
 }
 
-fn parse_updates(input: &str) -> Vec<Vec<&str>> {
-    let mut vec = Vec::new();
-    for line in input.lines() {
-        vec.push(line.trim().split(",").collect());
+/// An update read by [`RuleBook::process`], and how it fared against the rules at the time.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Check {
+    pub line: usize,
+    pub update: Update,
+    pub result: Result<(), Violation>,
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "couldn't read the next line: {error}"),
+            Self::Parse(error) => error.fmt(f),
+        }
     }
-    vec
 }
 
-fn parse_input(input: &str) -> (HashMap<(&str, &str), bool>, Vec<Vec<&str>>) {
-    let (rules, updates) = input.trim().split_once("\n\n").unwrap();
-    (parse_page_ordering_rules(rules), parse_updates(updates))
+impl Error for StreamError {}
+
+/// The iterator returned by [`RuleBook::process`], which yields a result for each update
+/// and an error for each line it can't parse, stopping at the end of the input or after
+/// the first error reading it.
+pub struct Checks<'book, R> {
+    rule_book: &'book mut RuleBook,
+    reader: R,
+    line: Vec<u8>,
+    line_number: usize,
+    finished: bool,
 }
 
-fn update_is_correct(update: &[&str], rules: &HashMap<(&str, &str), bool>) -> bool {
-    for i in 0..update.len() - 1 {
-        let ival = update[i];
-        for j in i + 1..update.len() {
-            if !rules.get(&(ival, update[j])).unwrap_or(&true) {
-                return false;
+impl<R: BufRead> Checks<'_, R> {
+    fn process_line(&mut self) -> Result<Option<Check>, ParseError> {
+        let line = self.line.trim_ascii();
+        if line.is_empty() {
+            return Ok(None);
+        }
+        let (retract, rule) = match line.strip_prefix(b"-") {
+            Some(rule) => (true, rule),
+            None => (false, line),
+        };
+        if retract || rule.contains(&b'|') {
+            let (before, after) = parse_rule(rule, self.line_number)?;
+            if retract {
+                self.rule_book.retract_rule(before, after);
+            } else {
+                self.rule_book.add_rule(before, after);
             }
+            return Ok(None);
         }
+        let update = parse_update(line, self.line_number)?;
+        Ok(Some(Check {
+            line: self.line_number,
+            result: self.rule_book.check(&update),
+            update,
+        }))
     }
-    true
 }
 
-fn select_middle_entry<'input>(update: &[&'input str]) -> &'input str {
-    update[update.len() / 2]
+impl<R: BufRead> Iterator for Checks<'_, R> {
+    type Item = Result<Check, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            self.line.clear();
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => {
+                    self.finished = true;
+                    return None;
+                }
+                Ok(_) => self.line_number += 1,
+                // there's no telling where the reader got to, so it isn't read from again.
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(StreamError::Io(error)));
+                }
+            }
+            match self.process_line() {
+                Ok(Some(check)) => return Some(Ok(check)),
+                Ok(None) => {}
+                Err(error) => return Some(Err(StreamError::Parse(error))),
+            }
+        }
+        None
+    }
 }
 
-fn parse_middle_entry(update: &[&str]) -> IntegerType {
-    select_middle_entry(update).parse().unwrap()
+impl<R: BufRead> FusedIterator for Checks<'_, R> {}
+
+fn parse_rule(line: &[u8], line_number: usize) -> Result<(IntegerType, IntegerType), ParseError> {
+    let (first, second) = bytes::split_once(line, b'|').ok_or(ParseError::Malformed {
+        line: line_number,
+        expected: "a rule like 47|53",
+    })?;
+    Ok((
+        parse_number(first, line_number)?,
+        parse_number(second, line_number)?,
+    ))
 }
 
-fn fix_incorrect_update<'input>(
-    update: &[&'input str],
-    rules: &HashMap<(&str, &str), bool>,
-) -> Vec<&'input str> {
-    let mut owned = update.to_owned();
-    owned.sort_by(|page_1, page_2| {
-        if *rules.get(&(page_1, page_2)).unwrap_or(&true) {
-            Ordering::Less
-        } else {
-            Ordering::Greater
+fn parse_update(line: &[u8], line_number: usize) -> Result<Update, ParseError> {
+    line.split(|&byte| byte == b',')
+        .map(|page| parse_number(page, line_number))
+        .collect()
+}
+
+fn parse_page_ordering_rules(input: &[u8]) -> Result<RuleBook, ParseError> {
+    let mut rules = RuleBook::new();
+    for (index, line) in bytes::lines(input).enumerate() {
+        let (first, second) = parse_rule(line, index + 1)?;
+        rules.add_rule(first, second);
+    }
+    Ok(rules)
+}
+
+fn parse_updates(input: &[u8], first_line: usize) -> Result<Vec<Update>, ParseError> {
+    let strict = conformance::is_strict();
+    let mut vec = Vec::new();
+    for (index, line) in bytes::lines(input).enumerate() {
+        let update = parse_update(line, first_line + index)?;
+        // otherwise the middle page is the later of the two in the middle.
+        if strict && update.len().is_multiple_of(2) {
+            return Err(ParseError::Nonconforming {
+                line: Some(first_line + index),
+                reason: format!("an update of {} pages has no middle page", update.len()),
+            });
         }
-    });
+        vec.push(update);
+    }
+    Ok(vec)
+}
+
+/// The rules and the updates from a puzzle input.
+pub fn parse_input(input: &str) -> Result<(RuleBook, Vec<Update>), ParseError> {
+    trace::span!("generator", day = 5);
+    let input = input.as_bytes();
+    match bytes::sections(input)[..] {
+        [rules, updates] => Ok((
+            parse_page_ordering_rules(rules.text)?,
+            parse_updates(updates.text, updates.first_line)?,
+        )),
+        [_, _, extra, ..] => Err(ParseError::Malformed {
+            line: extra.first_line,
+            expected: "nothing after the updates",
+        }),
+        _ => Err(ParseError::Malformed {
+            line: bytes::lines(input).count(),
+            expected: "a blank line between the rules and the updates",
+        }),
+    }
+}
+
+fn update_is_correct(update: &[IntegerType], rules: &RuleBook) -> bool {
+    match rules.check(update) {
+        Ok(()) => true,
+        #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+        Err(violation) => {
+            trace::event!(
+                debug,
+                ?update,
+                rule = %format_args!("{}|{}", violation.before, violation.after),
+                "update rejected"
+            );
+            false
+        }
+    }
+}
+
+fn select_middle_entry(update: &[IntegerType]) -> IntegerType {
+    update[update.len() / 2]
+}
+
+// An insertion sort rather than `sort_by`, because the rules only form a total order when
+// the input is well behaved, and the standard sorts may panic when given one that isn't.
+pub fn fix_incorrect_update(update: &[IntegerType], rules: &RuleBook) -> Update {
+    let mut owned: Update = Vec::with_capacity(update.len());
+    for &page in update {
+        let position = owned
+            .iter()
+            .position(|&other| compare_pages(page, other, rules) == Ordering::Less)
+            .unwrap_or(owned.len());
+        owned.insert(position, page);
+    }
     owned
 }
 
-#[aoc(day5, part1)]
-fn part1(input: &str) -> IntegerType {
-    let (rules, updates) = parse_input(input);
-    updates
-        .into_iter()
-        .filter_map(|update| {
-            if update_is_correct(&update, &rules) {
-                Some(parse_middle_entry(&update))
-            } else {
-                None
+/// One step in turning an update into a correct one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Edit {
+    /// Takes the page at `from` out of the update and puts it back in so that it ends up
+    /// at `to`.
+    Move { from: usize, to: usize },
+    /// Swaps the pages at `at` and `at + 1`.
+    Swap { at: usize },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Repair {
+    pub update: Update,
+    pub edits: Vec<Edit>,
+}
+
+pub fn apply_edits(update: &[IntegerType], edits: &[Edit]) -> Update {
+    let mut update = update.to_vec();
+    for edit in edits {
+        match *edit {
+            Edit::Move { from, to } => {
+                let page = update.remove(from);
+                update.insert(to, page);
             }
+            Edit::Swap { at } => update.swap(at, at + 1),
+        }
+    }
+    update
+}
+
+// Where each page ends up in the sorted update, with repeated pages kept in the same order.
+fn target_ranks(update: &[IntegerType], target: &[IntegerType]) -> Vec<usize> {
+    let mut used = vec![false; target.len()];
+    update
+        .iter()
+        .map(|page| {
+            let rank = (0..target.len())
+                .find(|&rank| !used[rank] && target[rank] == *page)
+                .unwrap();
+            used[rank] = true;
+            rank
         })
-        .sum()
+        .collect()
 }
 
-#[aoc(day5, part2)]
-fn part2(input: &str) -> u32 {
-    let (rules, updates) = parse_input(input);
-    updates
+// The indexes of a longest strictly increasing subsequence.
+fn longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+    // tails[length - 1] is the index of the smallest value ending a subsequence that long.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; values.len()];
+    for (index, value) in values.iter().enumerate() {
+        let length = tails.partition_point(|&tail| values[tail] < *value);
+        previous[index] = length.checked_sub(1).map(|length| tails[length]);
+        if length == tails.len() {
+            tails.push(index);
+        } else {
+            tails[length] = index;
+        }
+    }
+    let mut subsequence = Vec::with_capacity(tails.len());
+    let mut index = tails.last().copied();
+    while let Some(current) = index {
+        subsequence.push(current);
+        index = previous[current];
+    }
+    subsequence.reverse();
+    subsequence
+}
+
+/// Repairs an update by moving as few pages as possible, leaving the longest run of pages
+/// that are already in the right order relative to each other where they are. The result
+/// is the same as [`fix_incorrect_update`], and the number of moves is the least possible
+/// when the rules put all the pages of the update in a single order, as in the puzzle.
+pub fn repair_with_moves(update: &[IntegerType], rules: &RuleBook) -> Repair {
+    let target = fix_incorrect_update(update, rules);
+    let mut kept = vec![false; update.len()];
+    for index in longest_increasing_subsequence(&target_ranks(update, &target)) {
+        kept[index] = true;
+    }
+
+    // pages are moved in sorted order, each one to just after the page before it in the
+    // sorted update, so the pages in place so far are always in order.
+    let mut current: Vec<(usize, bool)> = target_ranks(update, &target)
         .into_iter()
-        .filter_map(|update| {
-            if !update_is_correct(&update, &rules) {
-                Some(parse_middle_entry(&fix_incorrect_update(&update, &rules)))
-            } else {
-                None
+        .zip(kept)
+        .collect();
+    let mut edits = Vec::new();
+    for rank in 0..target.len() {
+        let from = current
+            .iter()
+            .position(|&(other, _)| other == rank)
+            .unwrap();
+        if current[from].1 {
+            continue;
+        }
+        current.remove(from);
+        let to = current
+            .iter()
+            .rposition(|&(other, in_place)| in_place && other < rank)
+            .map_or(0, |index| index + 1);
+        current.insert(to, (rank, true));
+        edits.push(Edit::Move { from, to });
+    }
+    Repair {
+        update: target,
+        edits,
+    }
+}
+
+/// Repairs an update by swapping neighbouring pages, with the fewest swaps needed to get
+/// to the same result as [`fix_incorrect_update`].
+pub fn repair_with_swaps(update: &[IntegerType], rules: &RuleBook) -> Repair {
+    let target = fix_incorrect_update(update, rules);
+    let mut ranks = target_ranks(update, &target);
+    let mut edits = Vec::new();
+    // a bubble sort only ever swaps pages that are the wrong way round.
+    for end in (1..ranks.len()).rev() {
+        for at in 0..end {
+            if ranks[at] > ranks[at + 1] {
+                ranks.swap(at, at + 1);
+                edits.push(Edit::Swap { at });
             }
-        })
-        .sum()
+        }
+    }
+    Repair {
+        update: target,
+        edits,
+    }
+}
+
+fn compare_pages(page_1: IntegerType, page_2: IntegerType, rules: &RuleBook) -> Ordering {
+    if rules.requires(page_2, page_1) {
+        Ordering::Greater
+    } else {
+        Ordering::Less
+    }
+}
+
+fn sum_of_correct_middles(rules: &RuleBook, updates: &[Update]) -> Result<IntegerType, Overflow> {
+    trace::span!("part", day = 5, part = 1);
+    let middle_if_correct = |update: &Update| {
+        if update_is_correct(update, rules) {
+            Some(select_middle_entry(update))
+        } else {
+            None
+        }
+    };
+    parallelism::run(5, 1, Mode::Sequential, |parallel| {
+        if parallel {
+            updates
+                .par_iter()
+                .filter_map(middle_if_correct)
+                .map(Some)
+                .try_reduce(|| 0, IntegerType::checked_add)
+        } else {
+            updates
+                .iter()
+                .filter_map(middle_if_correct)
+                .try_fold(0, IntegerType::checked_add)
+        }
+    })
+    .ok_or(Overflow)
+}
+
+fn sum_of_fixed_middles(rules: &RuleBook, updates: &[Update]) -> Result<IntegerType, Overflow> {
+    trace::span!("part", day = 5, part = 2);
+    let fixed_middle_if_incorrect = |update: &Update| {
+        if !update_is_correct(update, rules) {
+            Some(select_middle_entry(&fix_incorrect_update(update, rules)))
+        } else {
+            None
+        }
+    };
+    parallelism::run(5, 2, Mode::Sequential, |parallel| {
+        if parallel {
+            updates
+                .par_iter()
+                .filter_map(fixed_middle_if_incorrect)
+                .map(Some)
+                .try_reduce(|| 0, IntegerType::checked_add)
+        } else {
+            updates
+                .iter()
+                .filter_map(fixed_middle_if_incorrect)
+                .try_fold(0, IntegerType::checked_add)
+        }
+    })
+    .ok_or(Overflow)
+}
+
+#[aoc(day5, part1)]
+pub fn part1(input: &str) -> Result<IntegerType, ParseError> {
+    let (rules, updates) = parse_input(input)?;
+    Ok(sum_of_correct_middles(&rules, &updates)?)
+}
+
+#[aoc(day5, part2)]
+pub fn part2(input: &str) -> Result<IntegerType, ParseError> {
+    let (rules, updates) = parse_input(input)?;
+    Ok(sum_of_fixed_middles(&rules, &updates)?)
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: u32 = 5;
+    type Input = (RuleBook, Vec<Update>);
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1((rules, updates): &Self::Input) -> Result<Answer, Overflow> {
+        sum_of_correct_middles(rules, updates).map(Answer::from)
+    }
+
+    fn part2((rules, updates): &Self::Input) -> Result<Answer, Overflow> {
+        sum_of_fixed_middles(rules, updates).map(Answer::from)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::conformance::{with_conformance, Conformance};
+    use crate::parallelism::{with_config, Config};
+    use proptest::prelude::*;
 
     const INPUT: &str = "
     47|53
@@ -130,14 +605,310 @@ mod tests {
     #[test]
     fn test_day_5_part_1() {
         const EXPECTED: IntegerType = 143;
-        let output = part1(INPUT);
+        let output = part1(INPUT).unwrap();
         assert_eq!(output, EXPECTED);
     }
 
+    #[test]
+    fn test_day_5_malformed_input() {
+        assert!(matches!(
+            part1("47|53\n75,47"),
+            Err(ParseError::Malformed { line: 2, .. })
+        ));
+        assert!(matches!(
+            part1("47|53\n97 13\n\n75,47"),
+            Err(ParseError::Malformed { line: 2, .. })
+        ));
+        assert_eq!(
+            part2("47|53\n\n53,47\n75,x,61").unwrap_err(),
+            ParseError::InvalidNumber {
+                line: 4,
+                text: "x".to_owned()
+            }
+        );
+    }
+
     #[test]
     fn test_day_5_part_2() {
         const EXPECTED: IntegerType = 123;
-        let output = part2(INPUT);
+        let output = part2(INPUT).unwrap();
         assert_eq!(output, EXPECTED);
     }
+
+    #[test]
+    fn test_day_5_strict_input() {
+        const INPUT: &str = "47|53\n\n47,53,61\n53,47";
+        assert_eq!(part2(INPUT), Ok(53));
+        assert_eq!(
+            with_conformance(Conformance::Strict, || part2(INPUT)).unwrap_err(),
+            ParseError::Nonconforming {
+                line: Some(4),
+                reason: "an update of 2 pages has no middle page".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn test_day_5_crlf_input() {
+        let input = "\u{feff}".to_owned() + &INPUT.replace('\n', "\r\n");
+        assert_eq!(part1(&input).unwrap(), 143);
+        assert_eq!(part2(&input).unwrap(), 123);
+    }
+
+    #[test]
+    fn test_day_5_minimal_repairs() {
+        let (rules, _) = parse_input(INPUT).unwrap();
+        let repair = repair_with_moves(&[97, 13, 75, 29, 47], &rules);
+        assert_eq!(repair.update, [97, 75, 47, 29, 13]);
+        assert_eq!(repair.edits.len(), 2);
+        assert_eq!(
+            apply_edits(&[97, 13, 75, 29, 47], &repair.edits),
+            repair.update
+        );
+        let repair = repair_with_swaps(&[97, 13, 75, 29, 47], &rules);
+        assert_eq!(repair.edits.len(), 4);
+        assert_eq!(
+            apply_edits(&[97, 13, 75, 29, 47], &repair.edits),
+            repair.update
+        );
+        assert_eq!(
+            repair_with_moves(&[75, 97, 47, 61, 53], &rules).edits,
+            [Edit::Move { from: 0, to: 1 }]
+        );
+        assert_eq!(
+            repair_with_swaps(&[61, 13, 29], &rules).edits,
+            [Edit::Swap { at: 1 }]
+        );
+        assert_eq!(repair_with_moves(&[75, 47, 61, 53, 29], &rules).edits, []);
+    }
+
+    #[test]
+    fn test_day_5_rule_book() {
+        let mut rules = RuleBook::new();
+        assert!(rules.add_rule(47, 53));
+        assert!(rules.add_rule(97, 53));
+        assert!(!rules.add_rule(47, 53));
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules.must_come_before(53).collect::<Vec<_>>(), [47, 97]);
+        assert_eq!(
+            rules.check(&[53, 61, 47]),
+            Err(Violation {
+                before: 47,
+                after: 53
+            })
+        );
+        assert!(rules.retract_rule(47, 53));
+        assert!(!rules.retract_rule(47, 53));
+        assert_eq!(rules.check(&[53, 61, 47]), Ok(()));
+        assert_eq!(rules.must_come_before(53).collect::<Vec<_>>(), [97]);
+        assert!(rules.retract_rule(97, 53));
+        assert!(rules.is_empty());
+        assert_eq!(rules.must_come_before(53).count(), 0);
+    }
+
+    #[test]
+    fn test_day_5_export() {
+        let mut rules = RuleBook::new();
+        rules.add_rule(97, 75);
+        rules.add_rule(47, 53);
+        rules.add_rule(75, 47);
+        assert_eq!(
+            rules.to_dot(None),
+            "digraph rules {\n    47 -> 53;\n    75 -> 47;\n    97 -> 75;\n}\n"
+        );
+        assert_eq!(
+            rules.to_dot(Some(&[75, 97, 47])),
+            "digraph rules {\n    75;\n    97;\n    47;\n    75 -> 47;\n    97 -> 75 [color=red];\n}\n"
+        );
+        assert_eq!(rules.to_json(None), r#"{"47":[53],"75":[47],"97":[75]}"#);
+        assert_eq!(rules.to_json(Some(&[47, 61])), r#"{"47":[],"61":[]}"#);
+    }
+
+    #[test]
+    fn test_day_5_rule_book_stream() {
+        const STREAM: &str = "47|53\n53,47\n\r\n-47|53\n53,47\n97|13\n13,x\n";
+        let mut rules = RuleBook::new();
+        let mut checks = rules.process(STREAM.as_bytes());
+        let check = checks.next().unwrap().unwrap();
+        assert_eq!((check.line, &check.update[..]), (2, &[53, 47][..]));
+        assert!(check.result.is_err());
+        let check = checks.next().unwrap().unwrap();
+        assert_eq!((check.line, check.result), (5, Ok(())));
+        assert!(matches!(
+            checks.next(),
+            Some(Err(StreamError::Parse(ParseError::InvalidNumber {
+                line: 7,
+                ..
+            })))
+        ));
+        assert!(checks.next().is_none());
+        assert!(rules.requires(97, 13));
+        assert!(!rules.requires(47, 53));
+    }
+
+    #[test]
+    fn test_day_5_rule_book_stream_read_error() {
+        struct Failing;
+
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+
+        let mut rules = RuleBook::new();
+        let mut checks = rules.process(io::BufReader::new(Failing));
+        assert!(matches!(checks.next(), Some(Err(StreamError::Io(_)))));
+        assert!(checks.next().is_none());
+        assert!(checks.next().is_none());
+    }
+
+    #[test]
+    fn test_day_5_overflow() {
+        let input = format!("1|2\n\n{0}\n{0}\n", IntegerType::MAX);
+        assert_eq!(part1(&input), Err(ParseError::Overflow));
+        let input = format!("2|{0}\n{0}|1\n\n1,{0},2\n1,{0},2\n", IntegerType::MAX);
+        for spec in ["sequential", "parallel"] {
+            let config = Config::parse(spec).unwrap();
+            assert_eq!(
+                with_config(config, || part2(&input)),
+                Err(ParseError::Overflow)
+            );
+        }
+    }
+
+    #[test]
+    fn test_day_5_parallelism() {
+        for spec in ["sequential", "parallel", "2"] {
+            let config = Config::parse(spec).unwrap();
+            assert_eq!(with_config(config.clone(), || part1(INPUT)).unwrap(), 143);
+            assert_eq!(with_config(config, || part2(INPUT)).unwrap(), 123);
+        }
+    }
+
+    // Pages in their intended order along with odd length updates drawn from them, some of
+    // them shuffled. As in the puzzle, there's a rule for every pair of pages.
+    fn manuals() -> impl Strategy<Value = (Vec<IntegerType>, Vec<Vec<IntegerType>>)> {
+        prop::sample::subsequence((10..100).collect::<Vec<IntegerType>>(), 3..15)
+            .prop_shuffle()
+            .prop_flat_map(|order| {
+                let update = prop::sample::subsequence(order.clone(), 1..=order.len())
+                    .prop_map(|mut update| {
+                        if update.len() % 2 == 0 {
+                            update.pop();
+                        }
+                        update
+                    })
+                    .prop_flat_map(|update| {
+                        (Just(update.clone()).prop_shuffle(), any::<bool>()).prop_map(
+                            move |(shuffled, keep_order)| {
+                                if keep_order {
+                                    update.clone()
+                                } else {
+                                    shuffled
+                                }
+                            },
+                        )
+                    });
+                (Just(order), prop::collection::vec(update, 1..10))
+            })
+    }
+
+    fn format_input(order: &[IntegerType], updates: &[Vec<IntegerType>]) -> String {
+        let mut input = String::new();
+        for (index, before) in order.iter().enumerate() {
+            for after in &order[index + 1..] {
+                input += &format!("{before}|{after}\n");
+            }
+        }
+        input += "\n";
+        for update in updates {
+            let pages: Vec<String> = update.iter().map(|page| page.to_string()).collect();
+            input += &(pages.join(",") + "\n");
+        }
+        input
+    }
+
+    fn sorted_by_order(update: &[IntegerType], order: &[IntegerType]) -> Vec<IntegerType> {
+        let mut sorted = update.to_vec();
+        sorted.sort_by_key(|page| order.iter().position(|other| other == page));
+        sorted
+    }
+
+    proptest! {
+        #[test]
+        fn test_day_5_fixed_updates_are_correct((order, updates) in manuals()) {
+            let input = format_input(&order, &updates);
+            let (rules, updates) = parse_input(&input).unwrap();
+            for update in updates {
+                prop_assert!(update_is_correct(&fix_incorrect_update(&update, &rules), &rules));
+            }
+        }
+
+        #[test]
+        fn test_day_5_part_1_matches_naive((order, updates) in manuals()) {
+            let expected: IntegerType = updates
+                .iter()
+                .filter(|update| sorted_by_order(update, &order) == **update)
+                .map(|update| update[update.len() / 2])
+                .sum();
+            prop_assert_eq!(part1(&format_input(&order, &updates)).unwrap(), expected);
+        }
+
+        #[test]
+        fn test_day_5_part_2_matches_naive((order, updates) in manuals()) {
+            let expected: IntegerType = updates
+                .iter()
+                .map(|update| (update, sorted_by_order(update, &order)))
+                .filter(|(update, sorted)| sorted != *update)
+                .map(|(_, sorted)| sorted[sorted.len() / 2])
+                .sum();
+            prop_assert_eq!(part2(&format_input(&order, &updates)).unwrap(), expected);
+        }
+
+        #[test]
+        fn test_day_5_parts_sum_to_sorted_middles((order, updates) in manuals()) {
+            let input = format_input(&order, &updates);
+            let expected: IntegerType = updates
+                .iter()
+                .map(|update| {
+                    let sorted = sorted_by_order(update, &order);
+                    sorted[sorted.len() / 2]
+                })
+                .sum();
+            prop_assert_eq!(part1(&input).unwrap() + part2(&input).unwrap(), expected);
+        }
+
+        #[test]
+        fn test_day_5_minimal_repairs_match_naive((order, updates) in manuals()) {
+            let (rules, updates) = parse_input(&format_input(&order, &updates)).unwrap();
+            for update in updates {
+                let sorted = sorted_by_order(&update, &order);
+                let moves = repair_with_moves(&update, &rules);
+                let swaps = repair_with_swaps(&update, &rules);
+                prop_assert_eq!(apply_edits(&update, &moves.edits), sorted.clone());
+                prop_assert_eq!(apply_edits(&update, &swaps.edits), sorted.clone());
+
+                // the pages that stay put have to already be in order, so try keeping every
+                // subset of them.
+                let most_kept = (0_u32..1 << update.len())
+                    .filter(|kept| {
+                        let kept: Vec<_> = (0..update.len())
+                            .filter(|index| kept & (1 << index) != 0)
+                            .map(|index| update[index])
+                            .collect();
+                        sorted_by_order(&kept, &order) == kept
+                    })
+                    .map(u32::count_ones)
+                    .max()
+                    .unwrap();
+                prop_assert_eq!(moves.edits.len(), update.len() - most_kept as usize);
+                let inversions = (0..update.len())
+                    .flat_map(|i| (i + 1..update.len()).map(move |j| (i, j)))
+                    .filter(|&(i, j)| sorted_by_order(&[update[i], update[j]], &order)[0] != update[i])
+                    .count();
+                prop_assert_eq!(swaps.edits.len(), inversions);
+            }
+        }
+    }
 }
@@ -1,165 +1,264 @@
+use crate::bytes;
+use crate::conformance;
+use crate::error::{Overflow, ParseError};
+use crate::parallelism::{self, Mode};
+use crate::solution::{Alternative, Solution};
+use crate::trace;
+use crate::Answer;
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 use rayon::prelude::*;
-use std::cell::{OnceCell, RefCell};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 // Bump this up if it crashes due to index out of range issues.
 // Not sure if all puzzle inputs are the same shape, but this lets us
 // have stack allocated storage for the grid instead of a heap allocation.
 const MAX_GRID_LENGTH: usize = 19_600;
 
-#[derive(Clone, Eq, Hash, PartialEq)]
-enum Direction {
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
     Up,
     Down,
     Left,
     Right,
 }
 
+impl Direction {
+    fn turned_right(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    fn reversed(self) -> Self {
+        self.turned_right().turned_right()
+    }
+
+    fn turned_left(self) -> Self {
+        self.reversed().turned_right()
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+}
+
+/// Which way a guard turns when there's an obstacle in front of them.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum TurningPolicy {
+    #[default]
+    Right,
+    Left,
+    Reverse,
+    /// Right, then left, then right again and so on.
+    Alternating,
+}
+
+/// What happens when a guard walks off an edge of the map.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Topology {
+    /// They leave the map.
+    #[default]
+    Bounded,
+    /// They come back in at the opposite edge.
+    Toroidal,
+}
+
+/// How guards move around the map. The default is the one from the puzzle.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Rules {
+    pub turning: TurningPolicy,
+    pub topology: Topology,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Guard {
+    pub position: (usize, usize),
+    pub direction: Direction,
+    // only used by the alternating turning policy.
+    turn_left_next: bool,
+}
+
+impl Guard {
+    fn new(position: (usize, usize), direction: Direction) -> Self {
+        Self {
+            position,
+            direction,
+            turn_left_next: false,
+        }
+    }
+
+    fn turn(&mut self, policy: TurningPolicy) {
+        self.direction = match policy {
+            TurningPolicy::Right => self.direction.turned_right(),
+            TurningPolicy::Left => self.direction.turned_left(),
+            TurningPolicy::Reverse => self.direction.reversed(),
+            TurningPolicy::Alternating => {
+                let direction = if self.turn_left_next {
+                    self.direction.turned_left()
+                } else {
+                    self.direction.turned_right()
+                };
+                self.turn_left_next = !self.turn_left_next;
+                direction
+            }
+        };
+    }
+}
+
 #[derive(Clone)]
-struct Map {
+pub struct Map {
     obstacles: [bool; MAX_GRID_LENGTH],
     number_of_columns: usize,
     number_of_rows: usize,
-    guard: ((usize, usize), Direction),
+    // the puzzle parts only follow the last guard on the map, as they always have.
+    guard: Guard,
+    guards: Vec<Guard>,
 }
 
 #[aoc_generator(day6)]
-fn input_generator(input: &str) -> Map {
+pub fn input_generator(input: &str) -> Result<Map, ParseError> {
+    trace::span!("generator", day = 6);
+    let strict = conformance::is_strict();
+    if strict {
+        conformance::check_grid(input.as_bytes(), b".#^>v<")?;
+    }
     let mut obstacles = [false; MAX_GRID_LENGTH];
-    let mut index = 0;
-    let mut number_of_columns = 0;
-    let mut number_of_rows = 1;
-    let mut lines = input.trim().lines().map(|line| line.trim());
-    let mut guard = (0, Direction::Up);
-
-    // do first line as a special case so it can also set number_of_columns.
-    // don't need to worry about that after first line as all rows have same number of columns.
-    {
-        for character in lines.next().unwrap().chars() {
-            number_of_columns += 1;
-            obstacles[index] = character == '#';
+    let lines = bytes::lines(input.as_bytes());
+    let mut guards = Vec::new();
+
+    // rows shorter than the widest one are treated as if they were padded out with empty
+    // cells, so that every guard ends up inside the rows by columns rectangle.
+    let number_of_columns = lines
+        .clone()
+        .map(<[u8]>::len)
+        .max()
+        .ok_or(ParseError::Empty)?;
+    let number_of_rows = lines.clone().count();
+    if number_of_rows * number_of_columns > MAX_GRID_LENGTH {
+        return Err(ParseError::TooLarge {
+            limit: MAX_GRID_LENGTH,
+        });
+    }
+
+    for (row, line) in lines.enumerate() {
+        for (column, &character) in line.iter().enumerate() {
+            let index = row * number_of_columns + column;
             match character {
-                '#' => obstacles[index] = true,
-                '>' => guard = (index, Direction::Right),
-                '<' => guard = (index, Direction::Left),
-                '^' => guard = (index, Direction::Up),
-                'v' => guard = (index, Direction::Down),
+                b'#' => obstacles[index] = true,
+                b'>' => guards.push((index, Direction::Right)),
+                b'<' => guards.push((index, Direction::Left)),
+                b'^' => guards.push((index, Direction::Up)),
+                b'v' => guards.push((index, Direction::Down)),
                 _ => {}
             }
-            index += 1;
         }
     }
 
-    // process rest of the lines in a similar fashion, but noneed to update number_of_columns.
-    for line in lines {
-        number_of_rows += 1;
-        for character in line.chars() {
-            match character {
-                '#' => obstacles[index] = true,
-                '>' => guard = (index, Direction::Right),
-                '<' => guard = (index, Direction::Left),
-                '^' => guard = (index, Direction::Up),
-                'v' => guard = (index, Direction::Down),
-                _ => {}
+    // the puzzle only ever has the one guard, so strict mode turns down any other number.
+    if strict {
+        match guards[..] {
+            [] => {
+                return Err(ParseError::Nonconforming {
+                    line: None,
+                    reason: "there's no guard on the map".to_owned(),
+                })
+            }
+            [_, (index, _), ..] => {
+                return Err(ParseError::Nonconforming {
+                    line: Some(index / number_of_columns + 1),
+                    reason: "there's more than one guard on the map".to_owned(),
+                })
             }
-            index += 1;
+            [_] => {}
         }
     }
-    let (guard_index, guard_direction) = guard;
-    let (row, column) = (
-        guard_index / number_of_columns,
-        guard_index % number_of_columns,
-    );
-    let guard = ((row, column), guard_direction);
 
-    Map {
+    let guards: Vec<Guard> = guards
+        .into_iter()
+        .map(|(index, direction)| {
+            let position = (index / number_of_columns, index % number_of_columns);
+            Guard::new(position, direction)
+        })
+        .collect();
+    let guard = guards
+        .last()
+        .copied()
+        .unwrap_or(Guard::new((0, 0), Direction::Up));
+
+    Ok(Map {
         obstacles,
         number_of_columns,
         number_of_rows,
         guard,
-    }
+        guards,
+    })
 }
 
-fn move_guard(guard: &mut ((usize, usize), Direction), input: &Map) -> bool {
-    let ((row, column), direction) = guard;
+fn next_position(
+    input: &Map,
+    (row, column): (usize, usize),
+    direction: Direction,
+    topology: Topology,
+) -> Option<(usize, usize)> {
+    let wraps = topology == Topology::Toroidal;
     match direction {
-        Direction::Left => {
-            if *column == 0 {
-                false
-            } else {
-                let new_column = *column - 1;
-                let index = *row * input.number_of_columns + new_column;
-                if input.obstacles[index] {
-                    *direction = Direction::Up;
-                } else {
-                    *column = new_column;
-                }
-                true
-            }
-        }
-        Direction::Right => {
-            if *column == input.number_of_columns - 1 {
-                false
-            } else {
-                let new_column = *column + 1;
-                let index = *row * input.number_of_columns + new_column;
-                if input.obstacles[index] {
-                    *direction = Direction::Down;
-                } else {
-                    *column = new_column;
-                }
-                true
-            }
-        }
-        Direction::Up => {
-            if *row == 0 {
-                false
-            } else {
-                let new_row = *row - 1;
-                let index = new_row * input.number_of_columns + *column;
-                if input.obstacles[index] {
-                    *direction = Direction::Right;
-                } else {
-                    *row = new_row;
-                }
-                true
-            }
-        }
-        Direction::Down => {
-            if *row == input.number_of_rows - 1 {
-                false
-            } else {
-                let new_row = *row + 1;
-                let index = new_row * input.number_of_columns + *column;
-                if input.obstacles[index] {
-                    *direction = Direction::Left;
-                } else {
-                    *row = new_row;
-                }
-                true
-            }
-        }
+        Direction::Up if row > 0 => Some((row - 1, column)),
+        Direction::Up if wraps => Some((input.number_of_rows - 1, column)),
+        Direction::Down if row + 1 < input.number_of_rows => Some((row + 1, column)),
+        Direction::Down if wraps => Some((0, column)),
+        Direction::Left if column > 0 => Some((row, column - 1)),
+        Direction::Left if wraps => Some((row, input.number_of_columns - 1)),
+        Direction::Right if column + 1 < input.number_of_columns => Some((row, column + 1)),
+        Direction::Right if wraps => Some((row, 0)),
+        _ => None,
+    }
+}
+
+// `extra_obstacles` are the indexes of obstacles to treat as being on the map without
+// having to copy the map to put them there.
+fn move_guard(guard: &mut Guard, input: &Map, rules: Rules, extra_obstacles: &[usize]) -> bool {
+    let Some((row, column)) = next_position(input, guard.position, guard.direction, rules.topology)
+    else {
+        return false;
+    };
+    let index = row * input.number_of_columns + column;
+    if input.obstacles[index] || extra_obstacles.contains(&index) {
+        guard.turn(rules.turning);
+    } else {
+        guard.position = (row, column);
     }
+    true
 }
 
+// There are only so many positions and directions the guard can be in, so once it has moved
+// more times than that it must be going round in circles and would never leave the map.
 fn get_distinct_positions(input: &Map) -> HashSet<(usize, usize)> {
     let mut visited = HashSet::new();
-    let mut guard = input.guard.clone();
-    visited.insert(guard.0);
-    while move_guard(&mut guard, input) {
-        visited.insert(guard.0);
+    let mut guard = input.guard;
+    let mut remaining_moves = 4 * input.number_of_rows * input.number_of_columns;
+    visited.insert(guard.position);
+    while remaining_moves > 0 && move_guard(&mut guard, input, Rules::default(), &[]) {
+        visited.insert(guard.position);
+        remaining_moves -= 1;
     }
     visited
 }
 
-fn has_cycle(input: &Map, visited: &mut HashSet<((usize, usize), Direction)>) -> bool {
+fn has_cycle(input: &Map, extra_obstacle: usize, visited: &mut HashSet<Guard>) -> bool {
     visited.clear();
-    let mut guard = input.guard.clone();
-    visited.insert(guard.clone());
-    while move_guard(&mut guard, input) {
-        if !visited.insert(guard.clone()) {
+    let mut guard = input.guard;
+    visited.insert(guard);
+    while move_guard(&mut guard, input, Rules::default(), &[extra_obstacle]) {
+        if !visited.insert(guard) {
             return true;
         }
     }
@@ -167,47 +266,469 @@ fn has_cycle(input: &Map, visited: &mut HashSet<((usize, usize), Direction)>) ->
 }
 
 #[aoc(day6, part1)]
-fn part1(input: &Map) -> usize {
+pub fn part1(input: &Map) -> usize {
+    trace::span!("part", day = 6, part = 1);
     get_distinct_positions(input).len()
 }
 
-#[aoc(day6, part2)]
-fn part2(input: &Map) -> usize {
-    thread_local! {
-        static VISITED: RefCell<HashSet<((usize, usize), Direction)>> = RefCell::new(HashSet::new());
-        static INPUT: RefCell<OnceCell<Map>> = RefCell::new(OnceCell::new());
-    }
-
-    get_distinct_positions(&input)
-        .par_iter()
-        .map(|position| {
-            if *position != input.guard.0 {
-                INPUT.with_borrow_mut(|input_mut| {
-                    let input_mut_ref = input_mut.get_mut();
-                    if input_mut_ref.is_none() {
-                        let _ = input_mut.set(input.clone());
-                    }
-                    let input = input_mut.get_mut().unwrap();
-                    let (row, column) = *position;
-                    let index = row * input.number_of_columns + column;
-                    input.obstacles[index] = true; // place obstacle.
-                    let return_value =
-                        VISITED.with_borrow_mut(
-                            |visited| if has_cycle(&input, visited) { 1 } else { 0 },
-                        );
-                    input.obstacles[index] = false; // unplace obstacle.
-                    return_value
+/// Every position where a single new obstacle would send the guard round in circles.
+pub fn loop_positions(input: &Map) -> HashSet<(usize, usize)> {
+    // The set of states is reused between positions to save on allocating it each time.
+    let creates_loop = |visited: &mut HashSet<_>, position: &&(usize, usize)| {
+        let (row, column) = **position;
+        let index = row * input.number_of_columns + column;
+        let creates_loop = **position != input.guard.position && has_cycle(input, index, visited);
+        if creates_loop {
+            trace::event!(debug, row, column, "loop detected with an obstacle");
+        }
+        creates_loop
+    };
+    let positions = get_distinct_positions(input);
+    parallelism::run(6, 2, Mode::PARALLEL, |parallel| {
+        if parallel {
+            positions
+                .par_iter()
+                .map_init(HashSet::new, |visited, position| {
+                    creates_loop(visited, &position).then_some(*position)
                 })
-            } else {
-                0
+                .flatten()
+                .collect()
+        } else {
+            let mut visited = HashSet::new();
+            positions
+                .iter()
+                .filter(|position| creates_loop(&mut visited, position))
+                .copied()
+                .collect()
+        }
+    })
+}
+
+#[aoc(day6, part2)]
+pub fn part2(input: &Map) -> usize {
+    trace::span!("part", day = 6, part = 2);
+    loop_positions(input).len()
+}
+
+// For each cell and direction, the index of the cell a guard walking that way from it stops
+// on in front of the next obstacle, or `None` if they'd walk off the map first.
+struct JumpTable {
+    stops: Vec<[Option<usize>; 4]>,
+}
+
+impl JumpTable {
+    fn new(input: &Map) -> Self {
+        let columns = input.number_of_columns;
+        let rows = input.number_of_rows;
+        let mut stops = vec![[None; 4]; rows * columns];
+        // each line is scanned from the end the guard is walking towards, remembering the
+        // cell in front of the last obstacle seen.
+        let mut scan = |cells: &mut dyn Iterator<Item = usize>, direction: Direction| {
+            let mut stop = None;
+            let mut after_obstacle = false;
+            for index in cells {
+                if input.obstacles[index] {
+                    after_obstacle = true;
+                    continue;
+                }
+                if after_obstacle {
+                    stop = Some(index);
+                    after_obstacle = false;
+                }
+                stops[index][direction.index()] = stop;
+            }
+        };
+        for column in 0..columns {
+            scan(
+                &mut (0..rows).map(|row| row * columns + column),
+                Direction::Up,
+            );
+            scan(
+                &mut (0..rows).rev().map(|row| row * columns + column),
+                Direction::Down,
+            );
+        }
+        for row in 0..rows {
+            scan(
+                &mut (0..columns).map(|column| row * columns + column),
+                Direction::Left,
+            );
+            scan(
+                &mut (0..columns).rev().map(|column| row * columns + column),
+                Direction::Right,
+            );
+        }
+        Self { stops }
+    }
+
+    // Where a guard stops with one extra obstacle on the map, which only matters if it's
+    // between them and the stop they'd otherwise make.
+    fn stop(
+        &self,
+        input: &Map,
+        position: (usize, usize),
+        direction: Direction,
+        extra_obstacle: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let stop = self.stops[to_index(input, position)][direction.index()]
+            .map(|index| to_position(input, index));
+        let (row, column) = position;
+        let (obstacle_row, obstacle_column) = extra_obstacle;
+        let blocked = match direction {
+            Direction::Up => {
+                obstacle_column == column
+                    && obstacle_row < row
+                    && stop.is_none_or(|(stop_row, _)| obstacle_row >= stop_row)
             }
+            Direction::Down => {
+                obstacle_column == column
+                    && obstacle_row > row
+                    && stop.is_none_or(|(stop_row, _)| obstacle_row <= stop_row)
+            }
+            Direction::Left => {
+                obstacle_row == row
+                    && obstacle_column < column
+                    && stop.is_none_or(|(_, stop_column)| obstacle_column >= stop_column)
+            }
+            Direction::Right => {
+                obstacle_row == row
+                    && obstacle_column > column
+                    && stop.is_none_or(|(_, stop_column)| obstacle_column <= stop_column)
+            }
+        };
+        if !blocked {
+            return stop;
+        }
+        Some(match direction {
+            Direction::Up => (obstacle_row + 1, column),
+            Direction::Down => (obstacle_row - 1, column),
+            Direction::Left => (row, obstacle_column + 1),
+            Direction::Right => (row, obstacle_column - 1),
         })
-        .sum()
+    }
+
+    // Only the states right after each turn are recorded, which is enough since a loop has
+    // to turn somewhere. `seen` holds the `round` in which each state was last visited so
+    // that it doesn't need clearing between obstacles.
+    fn has_cycle(
+        &self,
+        input: &Map,
+        extra_obstacle: (usize, usize),
+        seen: &mut [usize],
+        round: usize,
+    ) -> bool {
+        let mut position = input.guard.position;
+        let mut direction = input.guard.direction;
+        while let Some(stop) = self.stop(input, position, direction, extra_obstacle) {
+            position = stop;
+            direction = direction.turned_right();
+            let state = &mut seen[to_index(input, position) * 4 + direction.index()];
+            if *state == round {
+                return true;
+            }
+            *state = round;
+        }
+        false
+    }
+}
+
+/// The same as [`part2`], but jumping straight from one turn to the next with a table of
+/// where the guard stops in each direction from every cell rather than walking a step at a
+/// time.
+#[aoc(day6, part2, Jump)]
+pub fn part2_jump(input: &Map) -> usize {
+    trace::span!("part", day = 6, part = 2, implementation = "Jump");
+    let table = JumpTable::new(input);
+    let positions: Vec<_> = get_distinct_positions(input)
+        .into_iter()
+        .filter(|position| *position != input.guard.position)
+        .collect();
+    let states = 4 * input.number_of_rows * input.number_of_columns;
+    // rounds start at 1 so that a fresh `seen` doesn't look visited.
+    let creates_loop = |seen: &mut Vec<usize>, (round, position): (usize, &(usize, usize))| {
+        let creates_loop = table.has_cycle(input, *position, seen, round + 1);
+        if creates_loop {
+            trace::event!(
+                debug,
+                row = position.0,
+                column = position.1,
+                "loop detected with an obstacle"
+            );
+        }
+        creates_loop
+    };
+    parallelism::run(6, 2, Mode::PARALLEL, |parallel| {
+        if parallel {
+            positions
+                .par_iter()
+                .enumerate()
+                .map_init(|| vec![0; states], creates_loop)
+                .filter(|creates_loop| *creates_loop)
+                .count()
+        } else {
+            let mut seen = vec![0; states];
+            positions
+                .iter()
+                .enumerate()
+                .filter(|&position| creates_loop(&mut seen, position))
+                .count()
+        }
+    })
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// The guard left the map after taking this many steps, not counting turns.
+    Exits { steps: usize },
+    /// The guard ended up repeating the same this many moves forever.
+    Loops { length: usize },
+}
+
+struct Walk {
+    // each position the guard visits, in the order they're first visited.
+    positions: Vec<(usize, usize)>,
+    outcome: Outcome,
+}
+
+fn walk(input: &Map, extra_obstacles: &[usize]) -> Walk {
+    let mut guard = input.guard;
+    let mut states = HashMap::from([(guard, 0)]);
+    let mut positions = vec![guard.position];
+    let mut seen = HashSet::from([guard.position]);
+    let mut steps = 0;
+    let mut moves = 0;
+    let mut previous_position = guard.position;
+    while move_guard(&mut guard, input, Rules::default(), extra_obstacles) {
+        moves += 1;
+        if let Some(first_move) = states.insert(guard, moves) {
+            return Walk {
+                positions,
+                outcome: Outcome::Loops {
+                    length: moves - first_move,
+                },
+            };
+        }
+        if guard.position != previous_position {
+            steps += 1;
+            previous_position = guard.position;
+            if seen.insert(guard.position) {
+                positions.push(guard.position);
+            }
+        }
+    }
+    Walk {
+        positions,
+        outcome: Outcome::Exits { steps },
+    }
+}
+
+fn to_position(input: &Map, index: usize) -> (usize, usize) {
+    (
+        index / input.number_of_columns,
+        index % input.number_of_columns,
+    )
+}
+
+fn to_index(input: &Map, (row, column): (usize, usize)) -> usize {
+    row * input.number_of_columns + column
+}
+
+/// The fewest new obstacles, up to `max_obstacles`, that trap the guard in a loop of at most
+/// `max_loop_length` moves (turns included), or `None` if it can't be done with that many.
+/// This is a search over every combination of positions on the guard's path, so it's
+/// exponential in `max_obstacles` and best kept to small maps.
+pub fn min_obstacles_for_loop(
+    input: &Map,
+    max_loop_length: usize,
+    max_obstacles: usize,
+) -> Option<Vec<(usize, usize)>> {
+    // an obstacle only changes anything once the guard walks into it, so each new one only
+    // needs to be tried on the path the guard takes with the ones before it in place.
+    fn search(
+        input: &Map,
+        max_loop_length: usize,
+        remaining: usize,
+        placed: &mut Vec<usize>,
+    ) -> bool {
+        let walk = walk(input, placed);
+        if matches!(walk.outcome, Outcome::Loops { length } if length <= max_loop_length) {
+            return true;
+        }
+        if remaining == 0 {
+            return false;
+        }
+        for position in &walk.positions[1..] {
+            placed.push(to_index(input, *position));
+            if search(input, max_loop_length, remaining - 1, placed) {
+                return true;
+            }
+            placed.pop();
+        }
+        false
+    }
+
+    (0..=max_obstacles).find_map(|count| {
+        let mut placed = Vec::with_capacity(count);
+        search(input, max_loop_length, count, &mut placed).then(|| {
+            placed
+                .into_iter()
+                .map(|index| to_position(input, index))
+                .collect()
+        })
+    })
+}
+
+/// Where to put a single new obstacle so the guard takes the most steps before leaving the
+/// map, along with how many steps that is. Only positions on the guard's path are tried,
+/// since an obstacle anywhere else changes nothing, and ties go to whichever comes first on
+/// the path. `None` if every one of them would make the guard loop instead.
+pub fn longest_exit_placement(input: &Map) -> Option<((usize, usize), usize)> {
+    walk(input, &[]).positions[1..]
+        .iter()
+        .filter_map(
+            |position| match walk(input, &[to_index(input, *position)]).outcome {
+                Outcome::Exits { steps } => Some((*position, steps)),
+                Outcome::Loops { .. } => None,
+            },
+        )
+        .rev()
+        .max_by_key(|(_, steps)| *steps)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// The guard walked off the map from `position`.
+    Exited {
+        guard: usize,
+        position: (usize, usize),
+    },
+    /// Guards moved onto the same position. Guards don't block each other, so they carry on
+    /// from there as normal.
+    Collision {
+        guards: Vec<usize>,
+        position: (usize, usize),
+    },
+}
+
+/// Every guard on a map moving in lockstep, one move (a step or a turn) each per tick.
+/// Guards are numbered in the order they appear on the map.
+pub struct Patrol<'map> {
+    map: &'map Map,
+    rules: Rules,
+    guards: Vec<Option<Guard>>,
+    tick: usize,
+}
+
+impl<'map> Patrol<'map> {
+    pub fn new(map: &'map Map, rules: Rules) -> Self {
+        Self {
+            map,
+            rules,
+            guards: map.guards.iter().copied().map(Some).collect(),
+            tick: 0,
+        }
+    }
+
+    /// Each guard, or `None` once they've left the map.
+    pub fn guards(&self) -> &[Option<Guard>] {
+        &self.guards
+    }
+
+    pub fn tick(&self) -> usize {
+        self.tick
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.guards.iter().all(Option::is_none)
+    }
+
+    /// Moves every guard still on the map once. Guards that only turned don't collide
+    /// again with guards they were already sharing a position with.
+    pub fn step(&mut self) -> Vec<Event> {
+        self.tick += 1;
+        let mut events = Vec::new();
+        let mut moved = HashSet::new();
+        for (index, slot) in self.guards.iter_mut().enumerate() {
+            let Some(guard) = slot else {
+                continue;
+            };
+            let position = guard.position;
+            if !move_guard(guard, self.map, self.rules, &[]) {
+                events.push(Event::Exited {
+                    guard: index,
+                    position,
+                });
+                *slot = None;
+            } else if guard.position != position {
+                moved.insert(guard.position);
+            }
+        }
+
+        let mut guards_by_position: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (index, guard) in self.guards.iter().enumerate() {
+            if let Some(guard) = guard {
+                guards_by_position
+                    .entry(guard.position)
+                    .or_default()
+                    .push(index);
+            }
+        }
+        let mut collisions: Vec<_> = guards_by_position
+            .into_iter()
+            .filter(|(position, guards)| guards.len() > 1 && moved.contains(position))
+            .collect();
+        collisions.sort();
+        events.extend(
+            collisions
+                .into_iter()
+                .map(|(position, guards)| Event::Collision { guards, position }),
+        );
+        events
+    }
+
+    /// Steps until every guard has left the map or `max_ticks` have passed in total,
+    /// returning each event with the tick it happened on.
+    pub fn run(&mut self, max_ticks: usize) -> Vec<(usize, Event)> {
+        let mut events = Vec::new();
+        while self.tick < max_ticks && !self.is_finished() {
+            let tick = self.tick + 1;
+            events.extend(self.step().into_iter().map(|event| (tick, event)));
+        }
+        events
+    }
+}
+
+pub struct Day6;
+
+impl Solution for Day6 {
+    const DAY: u32 = 6;
+    type Input = Map;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, Overflow> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, Overflow> {
+        Ok(part2(input).into())
+    }
+
+    const ALTERNATIVES: &'static [Alternative<Self::Input>] = &[Alternative {
+        part: 2,
+        name: "Jump",
+        solve: |input| Ok(part2_jump(input).into()),
+    }];
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::conformance::{with_conformance, Conformance};
+    use crate::parallelism::{with_config, Config};
+    use proptest::prelude::*;
 
     const INPUT: &str = "
     ....#.....
@@ -225,14 +746,369 @@ mod tests {
     #[test]
     fn test_day_6_part_1() {
         const EXPECTED: usize = 41;
-        let output = part1(&input_generator(INPUT));
+        let output = part1(&input_generator(INPUT).unwrap());
         assert_eq!(output, EXPECTED);
     }
 
+    #[test]
+    fn test_day_6_malformed_input() {
+        assert!(matches!(input_generator(""), Err(ParseError::Empty)));
+        let too_large = "^".to_owned() + &"\n.".repeat(MAX_GRID_LENGTH);
+        assert!(matches!(
+            input_generator(&too_large),
+            Err(ParseError::TooLarge { .. })
+        ));
+    }
+
+    // A guard past the end of a short row used to land outside the map, which the jump
+    // table then indexed out of bounds.
+    #[test]
+    fn test_day_6_ragged_rows() {
+        let map = input_generator(".\n...^").unwrap();
+        assert_eq!((map.number_of_rows, map.number_of_columns), (2, 4));
+        assert_eq!(map.guard.position, (1, 3));
+        assert_eq!(part1(&map), 2);
+        assert_eq!(part2(&map), 0);
+        assert_eq!(part2_jump(&map), 0);
+    }
+
+    // Lenient parsing lays every row out at the widest row's width, so a ragged map reads
+    // the same as one with its short rows padded out with empty cells.
+    #[test]
+    fn test_day_6_lenient_ragged_rows() {
+        let lenient = |input| with_conformance(Conformance::Lenient, || input_generator(input));
+        let ragged = lenient("#.\n.\n..#.^").unwrap();
+        let padded = lenient("#....\n.....\n..#.^").unwrap();
+        assert_eq!((ragged.number_of_rows, ragged.number_of_columns), (3, 5));
+        assert_eq!(ragged.obstacles, padded.obstacles);
+        assert!(ragged.obstacles[0] && ragged.obstacles[2 * 5 + 2]);
+        assert_eq!(ragged.guard.position, (2, 4));
+        assert_eq!(ragged.guard.position, padded.guard.position);
+        assert_eq!(part1(&ragged), 3);
+        assert_eq!(part2(&ragged), part2(&padded));
+    }
+
+    #[test]
+    fn test_day_6_strict_input() {
+        let strict = |input| with_conformance(Conformance::Strict, || input_generator(input));
+        assert!(strict(INPUT).is_ok());
+        assert!(input_generator("^.v\n..").is_ok());
+        assert!(matches!(
+            strict("..#\n.^"),
+            Err(ParseError::Nonconforming { line: Some(2), .. })
+        ));
+        assert!(matches!(
+            strict("..#\n.^x"),
+            Err(ParseError::Nonconforming { line: Some(2), .. })
+        ));
+        assert_eq!(
+            strict("..#\n.^.\n>..").err().unwrap().to_string(),
+            "line 3: there's more than one guard on the map"
+        );
+        assert_eq!(
+            strict("..#\n...").err(),
+            Some(ParseError::Nonconforming {
+                line: None,
+                reason: "there's no guard on the map".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn test_day_6_several_guards() {
+        let map = input_generator(">...\n....\n...^").unwrap();
+        assert_eq!(map.guard.position, (2, 3));
+        assert_eq!(part1(&map), 3);
+        assert_eq!(Patrol::new(&map, Rules::default()).guards().len(), 2);
+    }
+
+    #[test]
+    fn test_day_6_guard_stuck_in_a_loop() {
+        const INPUT: &str = "
+        .#..
+        .^.#
+        ....
+        #...
+        ..#.
+        ";
+        assert_eq!(part1(&input_generator(INPUT).unwrap()), 6);
+    }
+
     #[test]
     fn test_day_6_part_2() {
         const EXPECTED: usize = 6;
-        let output = part2(&input_generator(INPUT));
+        let output = part2(&input_generator(INPUT).unwrap());
         assert_eq!(output, EXPECTED);
     }
+
+    #[test]
+    fn test_day_6_part_2_jump() {
+        let input = input_generator(INPUT).unwrap();
+        assert_eq!(part2_jump(&input), 6);
+        for spec in ["sequential", "parallel"] {
+            let config = Config::parse(spec).unwrap();
+            assert_eq!(with_config(config, || part2_jump(&input)), 6);
+        }
+    }
+
+    // Part 2 used to keep a copy of the first map it was given on each thread, so any later
+    // map got the answer for the first one.
+    #[test]
+    fn test_day_6_part_2_with_different_maps() {
+        const OTHER_INPUT: &str = "
+        .#...
+        ....#
+        .....
+        .^...
+        ...#.
+        ";
+        let map = input_generator(INPUT).unwrap();
+        let other_map = input_generator(OTHER_INPUT).unwrap();
+        for config in [Config::parse("sequential").unwrap(), Config::default()] {
+            with_config(config, || {
+                assert_eq!(part2(&map), 6);
+                assert_eq!(part2(&other_map), 1);
+                assert_eq!(part2(&map), 6);
+            });
+        }
+    }
+
+    #[test]
+    fn test_day_6_loop_positions() {
+        let map = input_generator(INPUT).unwrap();
+        assert_eq!(
+            loop_positions(&map),
+            HashSet::from([(6, 3), (7, 6), (7, 7), (8, 1), (8, 3), (9, 7)])
+        );
+    }
+
+    #[test]
+    fn test_day_6_min_obstacles_for_loop() {
+        let map = input_generator(INPUT).unwrap();
+        let obstacles = min_obstacles_for_loop(&map, usize::MAX, 2).unwrap();
+        assert_eq!(obstacles.len(), 1);
+        assert!(loop_positions(&map).contains(&obstacles[0]));
+        assert_eq!(min_obstacles_for_loop(&map, 3, 1), None);
+
+        // boxed in on every side, so the guard just turns on the spot.
+        let map = input_generator(".#.\n#^#\n.#.").unwrap();
+        assert_eq!(min_obstacles_for_loop(&map, 4, 0), Some(vec![]));
+        assert_eq!(min_obstacles_for_loop(&map, 3, 2), None);
+
+        // the only loop that short is turning on the spot where the guard starts.
+        let map = input_generator("...\n.^.\n...").unwrap();
+        let mut obstacles = min_obstacles_for_loop(&map, 4, 4).unwrap();
+        obstacles.sort();
+        assert_eq!(obstacles, [(0, 1), (1, 0), (1, 2), (2, 1)]);
+        assert_eq!(min_obstacles_for_loop(&map, 4, 3), None);
+    }
+
+    #[test]
+    fn test_day_6_longest_exit_placement() {
+        const INPUT: &str = "
+        ...
+        ...
+        .^.
+        ";
+        let map = input_generator(INPUT).unwrap();
+        assert_eq!(longest_exit_placement(&map), Some(((0, 1), 2)));
+        assert_eq!(longest_exit_placement(&input_generator("^").unwrap()), None);
+    }
+
+    #[test]
+    fn test_day_6_patrol_collisions() {
+        let map = input_generator(">...<").unwrap();
+        let events = Patrol::new(&map, Rules::default()).run(100);
+        assert_eq!(
+            events,
+            [
+                (
+                    2,
+                    Event::Collision {
+                        guards: vec![0, 1],
+                        position: (0, 2)
+                    }
+                ),
+                (
+                    5,
+                    Event::Exited {
+                        guard: 0,
+                        position: (0, 4)
+                    }
+                ),
+                (
+                    5,
+                    Event::Exited {
+                        guard: 1,
+                        position: (0, 0)
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_day_6_patrol_turning_policies() {
+        const INPUT: &str = "
+        .#.
+        .^.
+        ...
+        ";
+        let map = input_generator(INPUT).unwrap();
+        let exit = |turning| {
+            let rules = Rules {
+                turning,
+                ..Rules::default()
+            };
+            Patrol::new(&map, rules).run(100)
+        };
+        let exited_from = |position| vec![(3, Event::Exited { guard: 0, position })];
+        assert_eq!(exit(TurningPolicy::Right), exited_from((1, 2)));
+        assert_eq!(exit(TurningPolicy::Left), exited_from((1, 0)));
+        assert_eq!(exit(TurningPolicy::Reverse), exited_from((2, 1)));
+
+        // boxed in on three sides, alternating turns keep the guard facing the walls.
+        let map = input_generator(".#.\n#^#\n...").unwrap();
+        let rules = Rules {
+            turning: TurningPolicy::Alternating,
+            ..Rules::default()
+        };
+        let mut patrol = Patrol::new(&map, rules);
+        assert_eq!(patrol.run(10), []);
+        assert_eq!(patrol.guards()[0].unwrap().position, (1, 1));
+        let mut patrol = Patrol::new(&map, Rules::default());
+        assert_eq!(patrol.run(10).len(), 1);
+    }
+
+    #[test]
+    fn test_day_6_patrol_toroidal() {
+        let map = input_generator("..\n^.").unwrap();
+        let rules = Rules {
+            topology: Topology::Toroidal,
+            ..Rules::default()
+        };
+        let mut patrol = Patrol::new(&map, rules);
+        assert_eq!(patrol.run(9), []);
+        assert_eq!(patrol.tick(), 9);
+        assert_eq!(patrol.guards()[0].unwrap().position, (0, 0));
+        assert!(!patrol.is_finished());
+    }
+
+    #[test]
+    fn test_day_6_crlf_input() {
+        let input = "\u{feff}".to_owned() + &INPUT.replace('\n', "\r\n");
+        assert_eq!(part1(&input_generator(&input).unwrap()), 41);
+        assert_eq!(part2(&input_generator(&input).unwrap()), 6);
+    }
+
+    #[test]
+    fn test_day_6_parallelism() {
+        let input = input_generator(INPUT).unwrap();
+        for spec in ["sequential", "parallel", "2"] {
+            let config = Config::parse(spec).unwrap();
+            assert_eq!(with_config(config.clone(), || part1(&input)), 41);
+            assert_eq!(with_config(config, || part2(&input)), 6);
+        }
+    }
+
+    // Rows of '.' and '#' with exactly one guard placed somewhere on them.
+    fn guard_maps() -> impl Strategy<Value = Vec<Vec<char>>> {
+        (1..12_usize, 1..12_usize).prop_flat_map(|(rows, columns)| {
+            let cell = prop::sample::select(vec!['.', '.', '.', '.', '.', '#']);
+            (
+                prop::collection::vec(prop::collection::vec(cell, columns), rows),
+                0..rows,
+                0..columns,
+                prop::sample::select(vec!['^', 'v', '<', '>']),
+            )
+                .prop_map(|(mut grid, row, column, guard)| {
+                    grid[row][column] = guard;
+                    grid
+                })
+        })
+    }
+
+    fn format_input(grid: &[Vec<char>]) -> String {
+        grid.iter()
+            .map(|row| row.iter().collect::<String>() + "\n")
+            .collect()
+    }
+
+    // Walks the guard one cell at a time, returning the distinct cells visited before
+    // leaving the map, or `None` if the guard ends up going round in circles.
+    fn naive_walk(grid: &[Vec<char>]) -> Option<HashSet<(isize, isize)>> {
+        let (mut row, mut column, guard) = grid
+            .iter()
+            .enumerate()
+            .flat_map(|(row, cells)| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .map(move |(column, cell)| (row, column, *cell))
+            })
+            .find(|(_, _, cell)| "^v<>".contains(*cell))
+            .map(|(row, column, cell)| (row as isize, column as isize, cell))
+            .unwrap();
+        let mut step = match guard {
+            '^' => (-1, 0),
+            'v' => (1, 0),
+            '<' => (0, -1),
+            _ => (0, 1),
+        };
+        let cell = |row: isize, column: isize| {
+            grid.get(usize::try_from(row).ok()?)?
+                .get(usize::try_from(column).ok()?)
+                .copied()
+        };
+        let mut visited = HashSet::new();
+        let mut states = HashSet::new();
+        loop {
+            visited.insert((row, column));
+            if !states.insert((row, column, step)) {
+                return None;
+            }
+            match cell(row + step.0, column + step.1) {
+                None => return Some(visited),
+                Some('#') => step = (step.1, -step.0),
+                Some(_) => (row, column) = (row + step.0, column + step.1),
+            }
+        }
+    }
+
+    // Tries an obstacle on every empty cell.
+    fn naive_loop_positions(grid: &[Vec<char>]) -> usize {
+        let mut grid = grid.to_vec();
+        let mut count = 0;
+        for row in 0..grid.len() {
+            for column in 0..grid[row].len() {
+                if grid[row][column] == '.' {
+                    grid[row][column] = '#';
+                    count += usize::from(naive_walk(&grid).is_none());
+                    grid[row][column] = '.';
+                }
+            }
+        }
+        count
+    }
+
+    proptest! {
+        #[test]
+        fn test_day_6_part_1_matches_naive(grid in guard_maps()) {
+            let visited = naive_walk(&grid);
+            prop_assume!(visited.is_some());
+            prop_assert_eq!(part1(&input_generator(&format_input(&grid)).unwrap()), visited.unwrap().len());
+        }
+
+        #[test]
+        fn test_day_6_part_2_matches_naive(grid in guard_maps()) {
+            prop_assume!(naive_walk(&grid).is_some());
+            prop_assert_eq!(part2(&input_generator(&format_input(&grid)).unwrap()), naive_loop_positions(&grid));
+        }
+
+        #[test]
+        fn test_day_6_part_2_jump_matches_part_2(grid in guard_maps()) {
+            let input = input_generator(&format_input(&grid)).unwrap();
+            prop_assert_eq!(part2_jump(&input), part2(&input));
+        }
+    }
 }
@@ -1,3 +1,9 @@
+use crate::bytes;
+use crate::error::{parse_number, Overflow, ParseError};
+use crate::parallelism::{self, Mode};
+use crate::solution::{Alternative, Solution};
+use crate::trace::{self, Counter};
+use crate::Answer;
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 use rayon::prelude::*;
@@ -9,7 +15,7 @@ const MAX_TERMS_PER_EQUATION: usize = 12;
 // Tweak this if there's issues with incorrect answers, etc. due to overflow.
 type IntegerType = u64;
 
-struct Equations {
+pub struct Equations {
     equations: [Equation; MAX_NUMBER_OF_EQUATIONS],
     len: usize,
 }
@@ -32,105 +38,284 @@ impl Default for Equation {
 }
 
 #[aoc_generator(day7)]
-fn input_generator(input: &str) -> Equations {
+pub fn input_generator(input: &str) -> Result<Equations, ParseError> {
+    trace::span!("generator", day = 7);
     let mut equations_array = [Equation::default(); MAX_NUMBER_OF_EQUATIONS];
     let mut equations_len = 0;
-    for line in input.trim().lines() {
-        let (result, terms) = line.trim().split_once(": ").unwrap();
-        let result = result.parse().unwrap();
+    for (index, line) in bytes::lines(input.as_bytes()).enumerate() {
+        let (result, terms) = bytes::split_once(line, b':')
+            .and_then(|(result, terms)| Some((result, terms.strip_prefix(b" ")?)))
+            .ok_or(ParseError::Malformed {
+                line: index + 1,
+                expected: "an equation like 190: 10 19",
+            })?;
+        let result = parse_number(result, index + 1)?;
         let mut terms_array = [0; MAX_TERMS_PER_EQUATION];
         let mut terms_len = 0;
-        for term in terms.split(" ") {
-            terms_array[terms_len] = term.parse().unwrap();
+        for term in terms.split(|&byte| byte == b' ') {
+            *terms_array.get_mut(terms_len).ok_or(ParseError::TooLarge {
+                limit: MAX_TERMS_PER_EQUATION,
+            })? = parse_number(term, index + 1)?;
             terms_len += 1;
         }
-        equations_array[equations_len] = Equation {
+        *equations_array
+            .get_mut(equations_len)
+            .ok_or(ParseError::TooLarge {
+                limit: MAX_NUMBER_OF_EQUATIONS,
+            })? = Equation {
             result,
             terms: terms_array,
             len: terms_len,
         };
         equations_len += 1;
     }
-    Equations {
+    Ok(Equations {
         equations: equations_array,
         len: equations_len,
+    })
+}
+
+// The operators work on values that are `None` once they're too large for an
+// `IntegerType`, and so larger than any result. Adding or concatenating only ever makes
+// them larger still, so the only way back is multiplying by zero.
+fn add(current: Option<IntegerType>, term: IntegerType) -> Option<IntegerType> {
+    current?.checked_add(term)
+}
+
+fn multiply(current: Option<IntegerType>, term: IntegerType) -> Option<IntegerType> {
+    if term == 0 {
+        return Some(0);
     }
+    current?.checked_mul(term)
 }
 
-const fn concat(a: IntegerType, b: IntegerType) -> IntegerType {
-    a * (10 as IntegerType).pow(b.ilog10() + 1) + b
+// A zero on the right counts as a single digit.
+fn concat(current: Option<IntegerType>, term: IntegerType) -> Option<IntegerType> {
+    let digits = match term.checked_ilog10() {
+        Some(log) => log + 1,
+        None => 1,
+    };
+    current?
+        .checked_mul((10 as IntegerType).checked_pow(digits)?)?
+        .checked_add(term)
+}
+
+// The results of every satisfied equation added up, which can be too large to return even
+// though each result fits.
+fn total(
+    part: u32,
+    equations: &[Equation],
+    result_if_satisfied: impl Fn(&Equation) -> Option<IntegerType> + Send + Sync,
+) -> Result<IntegerType, Overflow> {
+    parallelism::run(7, part, Mode::PARALLEL, |parallel| {
+        if parallel {
+            equations
+                .par_iter()
+                .filter_map(result_if_satisfied)
+                .map(Some)
+                .try_reduce(|| 0, IntegerType::checked_add)
+        } else {
+            equations
+                .iter()
+                .filter_map(result_if_satisfied)
+                .try_fold(0, IntegerType::checked_add)
+        }
+    })
+    .ok_or(Overflow)
 }
 
 fn equation_satisfies_part_1(
     result: IntegerType,
-    current: IntegerType,
+    current: Option<IntegerType>,
     remaining_terms: &[IntegerType],
+    branches: &mut Counter,
 ) -> bool {
-    if remaining_terms.len() == 0 {
-        result == current
+    branches.increment();
+    if remaining_terms.is_empty() {
+        current == Some(result)
     } else {
         let (next_term, remaining_terms) = remaining_terms.split_at(1);
         let next_term = next_term[0];
-        equation_satisfies_part_1(result, current + next_term, remaining_terms)
-            || equation_satisfies_part_1(result, current * next_term, remaining_terms)
+        equation_satisfies_part_1(result, add(current, next_term), remaining_terms, branches)
+            || equation_satisfies_part_1(
+                result,
+                multiply(current, next_term),
+                remaining_terms,
+                branches,
+            )
     }
 }
 
 #[aoc(day7, part1)]
-fn part1(input: &Equations) -> IntegerType {
-    input.equations[..input.len]
-        .par_iter()
-        .filter_map(|equation| {
-            if equation_satisfies_part_1(
-                equation.result,
-                equation.terms[0],
-                &equation.terms[1..equation.len],
-            ) {
-                Some(equation.result)
-            } else {
-                None
-            }
-        })
-        .sum()
+pub fn part1(input: &Equations) -> Result<IntegerType, Overflow> {
+    trace::span!("part", day = 7, part = 1);
+    let equations = &input.equations[..input.len];
+    let result_if_satisfied = |equation: &Equation| {
+        let mut branches = Counter::default();
+        let satisfied = equation_satisfies_part_1(
+            equation.result,
+            Some(equation.terms[0]),
+            &equation.terms[1..equation.len],
+            &mut branches,
+        );
+        trace::event!(
+            debug,
+            result = equation.result,
+            satisfied,
+            branches = branches.count(),
+            "equation checked"
+        );
+        satisfied.then_some(equation.result)
+    };
+    total(1, equations, result_if_satisfied)
 }
 
 fn equation_satisfies_part_2(
     result: IntegerType,
-    current: IntegerType,
+    current: Option<IntegerType>,
     remaining_terms: &[IntegerType],
+    branches: &mut Counter,
 ) -> bool {
-    if remaining_terms.len() == 0 {
-        result == current
+    branches.increment();
+    if remaining_terms.is_empty() {
+        current == Some(result)
     } else {
         let (next_term, remaining_terms) = remaining_terms.split_at(1);
         let next_term = next_term[0];
-        equation_satisfies_part_2(result, current + next_term, remaining_terms)
-            || equation_satisfies_part_2(result, current * next_term, remaining_terms)
-            || equation_satisfies_part_2(result, concat(current, next_term), remaining_terms)
+        equation_satisfies_part_2(result, add(current, next_term), remaining_terms, branches)
+            || equation_satisfies_part_2(
+                result,
+                multiply(current, next_term),
+                remaining_terms,
+                branches,
+            )
+            || equation_satisfies_part_2(
+                result,
+                concat(current, next_term),
+                remaining_terms,
+                branches,
+            )
     }
 }
 
 #[aoc(day7, part2)]
-fn part2(input: &Equations) -> IntegerType {
-    input.equations[..input.len]
-        .par_iter()
-        .filter_map(|equation| {
-            if equation_satisfies_part_2(
-                equation.result,
-                equation.terms[0],
-                &equation.terms[1..equation.len],
-            ) {
-                Some(equation.result)
-            } else {
-                None
-            }
-        })
-        .sum()
+pub fn part2(input: &Equations) -> Result<IntegerType, Overflow> {
+    trace::span!("part", day = 7, part = 2);
+    let equations = &input.equations[..input.len];
+    let result_if_satisfied = |equation: &Equation| {
+        let mut branches = Counter::default();
+        let satisfied = equation_satisfies_part_2(
+            equation.result,
+            Some(equation.terms[0]),
+            &equation.terms[1..equation.len],
+            &mut branches,
+        );
+        trace::event!(
+            debug,
+            result = equation.result,
+            satisfied,
+            branches = branches.count(),
+            "equation checked"
+        );
+        satisfied.then_some(equation.result)
+    };
+    total(2, equations, result_if_satisfied)
+}
+
+// Works back from the result through the terms from last to first, undoing each operator
+// where it could have been used, which prunes most branches straight away.
+fn equation_satisfies_in_reverse(
+    result: IntegerType,
+    terms: &[IntegerType],
+    with_concatenation: bool,
+) -> bool {
+    let Some((&last, rest)) = terms.split_last() else {
+        return false;
+    };
+    if rest.is_empty() {
+        return result == last;
+    }
+    if result >= last && equation_satisfies_in_reverse(result - last, rest, with_concatenation) {
+        return true;
+    }
+    // anything times zero is zero.
+    if last == 0 {
+        if result == 0 {
+            return true;
+        }
+    } else if result.is_multiple_of(last)
+        && equation_satisfies_in_reverse(result / last, rest, with_concatenation)
+    {
+        return true;
+    }
+    if with_concatenation {
+        let digits = last.checked_ilog10().unwrap_or(0) + 1;
+        let (prefix, suffix) = match (10 as IntegerType).checked_pow(digits) {
+            Some(shift) => (result / shift, result % shift),
+            None => (0, result),
+        };
+        return suffix == last && equation_satisfies_in_reverse(prefix, rest, with_concatenation);
+    }
+    false
+}
+
+fn total_in_reverse(input: &Equations, part: u32) -> Result<IntegerType, Overflow> {
+    trace::span!("part", day = 7, part, implementation = "Reverse");
+    let equations = &input.equations[..input.len];
+    let result_if_satisfied = |equation: &Equation| {
+        equation_satisfies_in_reverse(equation.result, &equation.terms[..equation.len], part == 2)
+            .then_some(equation.result)
+    };
+    total(part, equations, result_if_satisfied)
+}
+
+#[aoc(day7, part1, Reverse)]
+pub fn part1_reverse(input: &Equations) -> Result<IntegerType, Overflow> {
+    total_in_reverse(input, 1)
+}
+
+#[aoc(day7, part2, Reverse)]
+pub fn part2_reverse(input: &Equations) -> Result<IntegerType, Overflow> {
+    total_in_reverse(input, 2)
+}
+
+pub struct Day7;
+
+impl Solution for Day7 {
+    const DAY: u32 = 7;
+    type Input = Equations;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, Overflow> {
+        part1(input).map(Answer::from)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, Overflow> {
+        part2(input).map(Answer::from)
+    }
+
+    const ALTERNATIVES: &'static [Alternative<Self::Input>] = &[
+        Alternative {
+            part: 1,
+            name: "Reverse",
+            solve: |input| part1_reverse(input).map(Answer::from),
+        },
+        Alternative {
+            part: 2,
+            name: "Reverse",
+            solve: |input| part2_reverse(input).map(Answer::from),
+        },
+    ];
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parallelism::{with_config, Config};
+    use proptest::prelude::*;
 
     const INPUT: &str = "
     190: 10 19
@@ -147,14 +332,177 @@ mod tests {
     #[test]
     fn test_day_7_part_1() {
         const EXPECTED: IntegerType = 3749;
-        let output = part1(&input_generator(INPUT));
+        let output = part1(&input_generator(INPUT).unwrap()).unwrap();
         assert_eq!(output, EXPECTED);
     }
 
+    #[test]
+    fn test_day_7_malformed_input() {
+        assert!(matches!(
+            input_generator("190: 10 19\n3267 81 40 27"),
+            Err(ParseError::Malformed { line: 2, .. })
+        ));
+        assert!(matches!(
+            input_generator("190: 10  19"),
+            Err(ParseError::InvalidNumber { line: 1, .. })
+        ));
+        assert!(matches!(
+            input_generator("1: 1 1 1 1 1 1 1 1 1 1 1 1 1"),
+            Err(ParseError::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_day_7_concat() {
+        assert_eq!(concat(Some(12), 345), Some(12345));
+        assert_eq!(concat(Some(12), 0), Some(120));
+        assert_eq!(concat(Some(IntegerType::MAX / 10 + 1), 0), None);
+    }
+
+    #[test]
+    fn test_day_7_overflow() {
+        // the search gets past the overflow by multiplying by zero.
+        let max = IntegerType::MAX;
+        let input = input_generator(&format!("0: {max} {max} 0\n")).unwrap();
+        assert_eq!(part1(&input), Ok(0));
+        assert_eq!(part2_reverse(&input), Ok(0));
+
+        let input = input_generator(&format!("{max}: {max}\n{max}: {max}\n")).unwrap();
+        for spec in ["sequential", "parallel"] {
+            let config = Config::parse(spec).unwrap();
+            assert_eq!(with_config(config.clone(), || part1(&input)), Err(Overflow));
+            assert_eq!(with_config(config, || part2_reverse(&input)), Err(Overflow));
+        }
+    }
+
     #[test]
     fn test_day_7_part_2() {
         const EXPECTED: IntegerType = 11387;
-        let output = part2(&input_generator(INPUT));
+        let output = part2(&input_generator(INPUT).unwrap()).unwrap();
         assert_eq!(output, EXPECTED);
     }
+
+    #[test]
+    fn test_day_7_reverse() {
+        let input = input_generator(INPUT).unwrap();
+        assert_eq!(part1_reverse(&input), Ok(3749));
+        assert_eq!(part2_reverse(&input), Ok(11387));
+        assert!(equation_satisfies_in_reverse(0, &[5, 0], false));
+        assert!(equation_satisfies_in_reverse(50, &[5, 0], true));
+        assert!(!equation_satisfies_in_reverse(
+            2,
+            &[1, IntegerType::MAX],
+            true
+        ));
+    }
+
+    #[test]
+    fn test_day_7_crlf_input() {
+        let input = "\u{feff}".to_owned() + &INPUT.replace('\n', "\r\n");
+        assert_eq!(part1(&input_generator(&input).unwrap()), Ok(3749));
+        assert_eq!(part2(&input_generator(&input).unwrap()), Ok(11387));
+    }
+
+    #[test]
+    fn test_day_7_parallelism() {
+        let input = input_generator(INPUT).unwrap();
+        for spec in ["sequential", "parallel", "2"] {
+            let config = Config::parse(spec).unwrap();
+            assert_eq!(with_config(config.clone(), || part1(&input)), Ok(3749));
+            assert_eq!(with_config(config, || part2(&input)), Ok(11387));
+        }
+    }
+
+    // Applies operators (0 = add, 1 = multiply, 2 = concatenate) left to right.
+    fn evaluate(terms: &[IntegerType], operators: &[u8]) -> IntegerType {
+        terms[1..].iter().zip(operators).fold(
+            terms[0],
+            |current, (term, operator)| match operator {
+                0 => current + term,
+                1 => current * term,
+                _ => concat(Some(current), *term).unwrap(),
+            },
+        )
+    }
+
+    // Equations whose result is either made up, or worked out from the terms with any of
+    // the three operators so that a good share of them can be satisfied.
+    fn equations() -> impl Strategy<Value = Vec<(IntegerType, Vec<IntegerType>)>> {
+        let equation = prop::collection::vec(1..100 as IntegerType, 1..6).prop_flat_map(|terms| {
+            let operators = prop::collection::vec(0..3_u8, terms.len() - 1);
+            (
+                Just(terms),
+                operators,
+                prop::option::of(1..10_000 as IntegerType),
+            )
+                .prop_map(|(terms, operators, made_up)| {
+                    (
+                        made_up.unwrap_or_else(|| evaluate(&terms, &operators)),
+                        terms,
+                    )
+                })
+        });
+        prop::collection::vec(equation, 1..20)
+    }
+
+    fn format_input(equations: &[(IntegerType, Vec<IntegerType>)]) -> String {
+        equations
+            .iter()
+            .map(|(result, terms)| {
+                let terms: Vec<String> = terms.iter().map(|term| term.to_string()).collect();
+                format!("{result}: {}\n", terms.join(" "))
+            })
+            .collect()
+    }
+
+    // Tries every combination of operators by counting in base `number_of_operators`.
+    fn naive_total(
+        equations: &[(IntegerType, Vec<IntegerType>)],
+        number_of_operators: u8,
+    ) -> IntegerType {
+        equations
+            .iter()
+            .filter(|(result, terms)| {
+                let slots = terms.len() as u32 - 1;
+                (0..(number_of_operators as u32).pow(slots)).any(|mut combination| {
+                    let operators: Vec<u8> = (0..slots)
+                        .map(|_| {
+                            let operator = (combination % number_of_operators as u32) as u8;
+                            combination /= number_of_operators as u32;
+                            operator
+                        })
+                        .collect();
+                    evaluate(terms, &operators) == *result
+                })
+            })
+            .map(|(result, _)| result)
+            .sum()
+    }
+
+    proptest! {
+        #[test]
+        fn test_day_7_part_1_matches_naive(equations in equations()) {
+            let input = input_generator(&format_input(&equations)).unwrap();
+            prop_assert_eq!(part1(&input), Ok(naive_total(&equations, 2)));
+        }
+
+        #[test]
+        fn test_day_7_part_2_matches_naive(equations in equations()) {
+            let input = input_generator(&format_input(&equations)).unwrap();
+            prop_assert_eq!(part2(&input), Ok(naive_total(&equations, 3)));
+        }
+
+        #[test]
+        fn test_day_7_reverse_matches_forward(equations in equations()) {
+            let input = input_generator(&format_input(&equations)).unwrap();
+            prop_assert_eq!(part1_reverse(&input), part1(&input));
+            prop_assert_eq!(part2_reverse(&input), part2(&input));
+        }
+
+        #[test]
+        fn test_day_7_part_1_never_exceeds_part_2(equations in equations()) {
+            let input = input_generator(&format_input(&equations)).unwrap();
+            prop_assert!(part1(&input).unwrap() <= part2(&input).unwrap());
+        }
+    }
 }
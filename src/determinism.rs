@@ -0,0 +1,200 @@
+//! Runs every implementation of a day's parts many times in one process, with different
+//! numbers of threads and in a different order each round, to catch answers that depend
+//! on how the work was scheduled or on what ran before.
+
+use crate::generate::Rng;
+use crate::parallelism::{self, Config, Mode};
+use crate::solution::{self, DynSolution, PartResult};
+use crate::ParseError;
+use std::fmt;
+
+pub struct Options {
+    pub rounds: usize,
+    /// Every implementation is run in each of these modes once per round.
+    pub modes: Vec<Mode>,
+    /// Seeds the order the runs are shuffled into.
+    pub seed: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            rounds: 3,
+            modes: vec![
+                Mode::Sequential,
+                Mode::Parallel { threads: Some(1) },
+                Mode::Parallel { threads: Some(2) },
+                Mode::Parallel { threads: Some(4) },
+                Mode::PARALLEL,
+            ],
+            seed: 0,
+        }
+    }
+}
+
+/// Every answer one implementation of a part gave for one input.
+pub struct Repeatability {
+    pub day: u32,
+    /// The index of the input this is for, counting from 1.
+    pub input: usize,
+    pub part: u32,
+    pub name: &'static str,
+    /// Each different answer, with the round and mode of every run that gave it.
+    pub answers: Vec<(PartResult, Vec<(usize, Mode)>)>,
+}
+
+impl Repeatability {
+    pub fn is_deterministic(&self) -> bool {
+        self.answers.len() <= 1
+    }
+}
+
+impl fmt::Display for Repeatability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Day {} - Part {} - {}, input {}",
+            self.day, self.part, self.name, self.input
+        )?;
+        if let [(answer, runs)] = &self.answers[..] {
+            return write!(
+                f,
+                ": the same in all {} runs: {}",
+                runs.len(),
+                solution::describe(answer)
+            );
+        }
+        let total: usize = self.answers.iter().map(|(_, runs)| runs.len()).sum();
+        write!(f, ": {} different answers", self.answers.len())?;
+        for (answer, runs) in &self.answers {
+            let (round, mode) = runs[0];
+            write!(
+                f,
+                "\n\tin {} of {total} runs, first in round {round} with {mode}: {}",
+                runs.len(),
+                solution::describe(answer)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs each implementation of both parts on each of `inputs` in every mode, round after
+/// round. Each round parses the inputs again and then shuffles all of the runs together,
+/// so anything one run leaves behind can change the answer of whichever comes next.
+pub fn check(
+    solution: &dyn DynSolution,
+    inputs: &[&str],
+    options: &Options,
+) -> Result<Vec<Repeatability>, ParseError> {
+    let mut results = Vec::new();
+    for input in 1..=inputs.len() {
+        for part in [1, 2] {
+            for name in solution.implementations(part) {
+                results.push(Repeatability {
+                    day: solution.day(),
+                    input,
+                    part,
+                    name,
+                    answers: Vec::new(),
+                });
+            }
+        }
+    }
+    let mut runs: Vec<(usize, Mode)> = (0..results.len())
+        .flat_map(|index| options.modes.iter().map(move |mode| (index, *mode)))
+        .collect();
+
+    let mut rng = Rng::new(options.seed);
+    for round in 1..=options.rounds {
+        let parsed = inputs
+            .iter()
+            .map(|input| solution.parse(input))
+            .collect::<Result<Vec<_>, _>>()?;
+        rng.shuffle(&mut runs);
+        for &(index, mode) in &runs {
+            let result = &mut results[index];
+            let answer = parallelism::with_config(Config::all(mode), || {
+                solution.run(result.part, result.name, &*parsed[result.input - 1])
+            })
+            .unwrap();
+            match result
+                .answers
+                .iter_mut()
+                .find(|(existing, _)| *existing == answer)
+            {
+                Some((_, runs)) => runs.push((round, mode)),
+                None => result.answers.push((answer, vec![(round, mode)])),
+            }
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Overflow;
+    use crate::solution::{Registered, Solution};
+    use crate::Answer;
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    static CALLS: AtomicI64 = AtomicI64::new(0);
+
+    // Part 1 is the same every time, but part 2 counts how often it's been called.
+    struct Counting;
+
+    impl Solution for Counting {
+        const DAY: u32 = 25;
+        type Input = i64;
+
+        fn parse(input: &str) -> Result<Self::Input, ParseError> {
+            Ok(input.len() as i64)
+        }
+
+        fn part1(input: &Self::Input) -> Result<Answer, Overflow> {
+            Ok(Answer::from(*input))
+        }
+
+        fn part2(input: &Self::Input) -> Result<Answer, Overflow> {
+            Ok(Answer::from(
+                *input + CALLS.fetch_add(1, Ordering::Relaxed).min(1),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let options = Options {
+            rounds: 2,
+            ..Options::default()
+        };
+        let results = check(&Registered::<Counting>::new(), &["a", "bc"], &options).unwrap();
+        assert_eq!(results.len(), 4);
+        let inputs: Vec<_> = results.iter().map(|result| result.input).collect();
+        assert_eq!(inputs, [1, 1, 2, 2]);
+        assert!(results
+            .iter()
+            .all(|result| result.part == 2 || result.is_deterministic()));
+        assert!(results[0]
+            .to_string()
+            .starts_with("Day 25 - Part 1 - default, input 1: the same in all 10 runs: 1"));
+
+        let flaky: Vec<_> = results
+            .iter()
+            .filter(|result| !result.is_deterministic())
+            .collect();
+        assert_eq!(flaky.len(), 1);
+        assert_eq!(flaky[0].answers[0].1.len(), 1);
+        assert_eq!(flaky[0].answers[1].1.len(), 9);
+        assert!(flaky[0]
+            .to_string()
+            .contains(": 2 different answers\n\tin 1 of 10 runs, first in round 1 with "));
+
+        let day7 = crate::solution::get(7).unwrap();
+        assert!(matches!(
+            check(day7, &["190: 10 19", "oops"], &options),
+            Err(ParseError::Malformed { .. })
+        ));
+    }
+}
@@ -0,0 +1,26 @@
+//! The puzzle inputs from `input/2024` as they were when the crate was built with the
+//! `embedded-inputs` feature, so that the binary can run without them alongside it.
+
+include!(concat!(env!("OUT_DIR"), "/inputs.rs"));
+
+/// The input for `day`, or `None` if there wasn't one at build time.
+pub fn input(day: u32) -> Option<&'static str> {
+    INPUTS
+        .iter()
+        .find(|(input_day, _)| *input_day == day)
+        .map(|(_, input)| *input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_embedded_inputs() {
+        for day in 1..=25 {
+            let file = fs::read_to_string(format!("input/2024/day{day}.txt")).ok();
+            assert_eq!(input(day), file.as_deref());
+        }
+    }
+}
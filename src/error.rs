@@ -0,0 +1,82 @@
+use crate::bytes::{self, Integer};
+use std::error::Error;
+use std::fmt;
+
+/// Why a puzzle input couldn't be parsed. Line numbers start from 1 at the first line
+/// that isn't blank, since inputs are trimmed before they're parsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseError {
+    Empty,
+    InvalidNumber {
+        line: usize,
+        text: String,
+    },
+    Malformed {
+        line: usize,
+        expected: &'static str,
+    },
+    TooLarge {
+        limit: usize,
+    },
+    /// An [`Overflow`] from a part that parses its own input.
+    Overflow,
+    /// The input breaks one of the puzzle's rules, which is only checked in strict
+    /// [`Conformance`](crate::conformance::Conformance). Not every rule is about a line.
+    Nonconforming {
+        line: Option<usize>,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "input is empty"),
+            Self::InvalidNumber { line, text } => {
+                write!(f, "line {line}: '{text}' isn't a valid number")
+            }
+            Self::Malformed { line, expected } => write!(f, "line {line}: expected {expected}"),
+            Self::TooLarge { limit } => {
+                write!(
+                    f,
+                    "input is larger than the {limit} entries this solution supports"
+                )
+            }
+            Self::Overflow => write!(f, "the answer is too large for this solution"),
+            Self::Nonconforming {
+                line: Some(line),
+                reason,
+            } => write!(f, "line {line}: {reason}"),
+            Self::Nonconforming { line: None, reason } => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// The answer is too large for the type a part works it out in, which only happens with
+/// inputs far outside what the puzzle gives.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the answer is too large for this solution")
+    }
+}
+
+impl Error for Overflow {}
+
+impl From<Overflow> for ParseError {
+    fn from(Overflow: Overflow) -> Self {
+        Self::Overflow
+    }
+}
+
+/// Parses a number out of the given line of the input.
+pub(crate) fn parse_number<T: Integer>(text: &[u8], line: usize) -> Result<T, ParseError> {
+    bytes::parse(text).ok_or_else(|| ParseError::InvalidNumber {
+        line,
+        text: String::from_utf8_lossy(text).into_owned(),
+    })
+}
@@ -0,0 +1,344 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const YEAR: u32 = 2024;
+const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+const DEFAULT_INPUT_DIRECTORY: &str = "input/2024";
+const SESSION_ENV_VAR: &str = "AOC_SESSION";
+const SESSION_FILE_ENV_VAR: &str = "AOC_SESSION_FILE";
+const USER_AGENT: &str = "github.com/ForgottenMaster/aoc-2024";
+
+// The site asks that automated tools don't hammer it, so space requests out.
+const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum FetchError {
+    MissingSession,
+    RateLimited,
+    Status(u16),
+    Transport(String),
+    NoExample,
+    Io(io::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSession => write!(
+                f,
+                "no session token found, set {SESSION_ENV_VAR} or put it in {}",
+                default_session_file().display()
+            ),
+            Self::RateLimited => write!(f, "rate limited by the server, try again later"),
+            Self::Status(code) => write!(f, "server responded with status {code}"),
+            Self::Transport(message) => write!(f, "request failed: {message}"),
+            Self::NoExample => write!(f, "couldn't find an example in the puzzle description"),
+            Self::Io(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for FetchError {}
+
+impl From<io::Error> for FetchError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+fn default_session_file() -> PathBuf {
+    let home = env::var_os("HOME").unwrap_or_default();
+    Path::new(&home).join(".config/aoc/session")
+}
+
+/// Looks up the session cookie in the `AOC_SESSION` environment variable, then in the
+/// file named by `AOC_SESSION_FILE`, then in `~/.config/aoc/session`.
+pub fn find_session() -> Result<String, FetchError> {
+    if let Ok(session) = env::var(SESSION_ENV_VAR) {
+        if !session.trim().is_empty() {
+            return Ok(session.trim().to_owned());
+        }
+    }
+    let path = env::var_os(SESSION_FILE_ENV_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(default_session_file);
+    match fs::read_to_string(path) {
+        Ok(session) if !session.trim().is_empty() => Ok(session.trim().to_owned()),
+        _ => Err(FetchError::MissingSession),
+    }
+}
+
+pub struct Fetcher {
+    agent: ureq::Agent,
+    session: String,
+    base_url: String,
+    input_directory: PathBuf,
+    min_interval: Duration,
+    last_request: Option<Instant>,
+}
+
+impl Fetcher {
+    pub fn new(session: impl Into<String>) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().user_agent(USER_AGENT).build(),
+            session: session.into(),
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            input_directory: PathBuf::from(DEFAULT_INPUT_DIRECTORY),
+            min_interval: DEFAULT_MIN_INTERVAL,
+            last_request: None,
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn with_input_directory(mut self, input_directory: impl Into<PathBuf>) -> Self {
+        self.input_directory = input_directory.into();
+        self
+    }
+
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    pub fn input_path(&self, day: u32) -> PathBuf {
+        self.input_directory.join(format!("day{day}.txt"))
+    }
+
+    pub fn example_path(&self, day: u32) -> PathBuf {
+        self.input_directory.join(format!("day{day}.example.txt"))
+    }
+
+    /// Downloads the puzzle input for the day unless it's already cached, and returns its path.
+    /// An empty file is the placeholder `aoc-tools new-day` leaves, so it's downloaded over.
+    pub fn fetch_input(&mut self, day: u32) -> Result<PathBuf, FetchError> {
+        let path = self.input_path(day);
+        if !fs::metadata(&path).is_ok_and(|metadata| metadata.len() > 0) {
+            let input = self.get(&format!("/{YEAR}/day/{day}/input"))?;
+            write_file(&path, &input)?;
+        }
+        Ok(path)
+    }
+
+    /// Downloads the puzzle description for the day unless the example is already cached,
+    /// and stores the first code block from it, which is the example in every puzzle so far.
+    pub fn fetch_example(&mut self, day: u32) -> Result<PathBuf, FetchError> {
+        let path = self.example_path(day);
+        if !path.exists() {
+            let page = self.get(&format!("/{YEAR}/day/{day}"))?;
+            let example = extract_example(&page).ok_or(FetchError::NoExample)?;
+            write_file(&path, &example)?;
+        }
+        Ok(path)
+    }
+
+    fn get(&mut self, path: &str) -> Result<String, FetchError> {
+        if let Some(last_request) = self.last_request {
+            let elapsed = last_request.elapsed();
+            if elapsed < self.min_interval {
+                thread::sleep(self.min_interval - elapsed);
+            }
+        }
+        self.last_request = Some(Instant::now());
+
+        let response = self
+            .agent
+            .get(&format!("{}{path}", self.base_url))
+            .set("Cookie", &format!("session={}", self.session))
+            .call();
+        match response {
+            Ok(response) => Ok(response.into_string()?),
+            Err(ureq::Error::Status(429, _)) => Err(FetchError::RateLimited),
+            Err(ureq::Error::Status(code, _)) => Err(FetchError::Status(code)),
+            Err(ureq::Error::Transport(transport)) => {
+                Err(FetchError::Transport(transport.to_string()))
+            }
+        }
+    }
+}
+
+fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)
+}
+
+fn extract_example(page: &str) -> Option<String> {
+    let (_, rest) = page.split_once("<pre><code>")?;
+    let (example, _) = rest.split_once("</code></pre>")?;
+    let example = example.replace("<em>", "").replace("</em>", "");
+    Some(
+        example
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    // Stand-in for the puzzle site that serves canned responses and records the
+    // requests it received, so the fetcher can be exercised without network access.
+    struct MockServer {
+        base_url: String,
+        requests: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl MockServer {
+        fn start(routes: &[(&str, u16, &str)]) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let base_url = format!("http://{}", listener.local_addr().unwrap());
+            let routes: Vec<(String, u16, String)> = routes
+                .iter()
+                .map(|(path, status, body)| (path.to_string(), *status, body.to_string()))
+                .collect();
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let recorded = Arc::clone(&requests);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut reader = BufReader::new(&stream);
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).unwrap();
+                    let path = request_line.split(' ').nth(1).unwrap_or("").to_owned();
+                    let mut cookie = String::new();
+                    loop {
+                        let mut header = String::new();
+                        reader.read_line(&mut header).unwrap();
+                        if header.trim().is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = header.split_once(':') {
+                            if name.eq_ignore_ascii_case("cookie") {
+                                cookie = value.trim().to_owned();
+                            }
+                        }
+                    }
+                    let (status, body) = routes
+                        .iter()
+                        .find(|(route, _, _)| *route == path)
+                        .map(|(_, status, body)| (*status, body.as_str()))
+                        .unwrap_or((404, "not found"));
+                    recorded.lock().unwrap().push((path, cookie));
+                    write!(
+                        stream,
+                        "HTTP/1.1 {status} Mock\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                    .unwrap();
+                }
+            });
+            Self { base_url, requests }
+        }
+
+        fn requests(&self) -> Vec<(String, String)> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    fn temp_directory(name: &str) -> PathBuf {
+        let directory =
+            env::temp_dir().join(format!("aoc-2024-fetch-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    fn fetcher(server: &MockServer, directory: &Path) -> Fetcher {
+        Fetcher::new("abc123")
+            .with_base_url(&server.base_url)
+            .with_input_directory(directory)
+            .with_min_interval(Duration::ZERO)
+    }
+
+    #[test]
+    fn test_fetch_input_caches() {
+        let server = MockServer::start(&[("/2024/day/1/input", 200, "3   4\n4   3\n")]);
+        let directory = temp_directory("caches");
+        let mut fetcher = fetcher(&server, &directory);
+
+        let path = fetcher.fetch_input(1).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "3   4\n4   3\n");
+        fetcher.fetch_input(1).unwrap();
+
+        assert_eq!(
+            server.requests(),
+            [("/2024/day/1/input".to_owned(), "session=abc123".to_owned())]
+        );
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_input_replaces_placeholder() {
+        let server = MockServer::start(&[("/2024/day/1/input", 200, "3   4\n")]);
+        let directory = temp_directory("placeholder");
+        let mut fetcher = fetcher(&server, &directory);
+        fs::create_dir_all(fetcher.input_path(1).parent().unwrap()).unwrap();
+        fs::write(fetcher.input_path(1), "").unwrap();
+
+        let path = fetcher.fetch_input(1).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "3   4\n");
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_example() {
+        const PAGE: &str = "<p>For example:</p>\n<pre><code>xmul(2,4)&amp;mul[3,7]!^don't()_<em>mul(5,5)</em>\n</code></pre><pre><code>other</code></pre>";
+        let server = MockServer::start(&[("/2024/day/3", 200, PAGE)]);
+        let directory = temp_directory("example");
+        let mut fetcher = fetcher(&server, &directory);
+
+        let path = fetcher.fetch_example(3).unwrap();
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)\n"
+        );
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_errors() {
+        let server = MockServer::start(&[("/2024/day/2/input", 429, "slow down")]);
+        let directory = temp_directory("errors");
+        let mut fetcher = fetcher(&server, &directory);
+
+        assert!(matches!(
+            fetcher.fetch_input(2),
+            Err(FetchError::RateLimited)
+        ));
+        assert!(matches!(
+            fetcher.fetch_input(25),
+            Err(FetchError::Status(404))
+        ));
+        assert!(!fetcher.input_path(2).exists());
+    }
+
+    #[test]
+    fn test_requests_are_spaced_out() {
+        let server = MockServer::start(&[]);
+        let directory = temp_directory("spacing");
+        let mut fetcher =
+            fetcher(&server, &directory).with_min_interval(Duration::from_millis(200));
+
+        let start = Instant::now();
+        let _ = fetcher.fetch_input(1);
+        let _ = fetcher.fetch_input(2);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+}
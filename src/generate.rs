@@ -0,0 +1,586 @@
+use crate::Answer;
+use std::collections::{HashMap, HashSet};
+
+/// A small, seedable random number generator (SplitMix64), so that the same seed always
+/// produces the same input regardless of platform or dependency versions.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `low..=high`.
+    pub fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next_u64() % (high - low + 1)
+    }
+
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.next_u64() % 100 < percent
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0, items.len() as u64 - 1) as usize]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            items.swap(index, self.range(0, index as u64) as usize);
+        }
+    }
+}
+
+/// A synthetic puzzle input, along with the answers to both parts when the generator
+/// knows them by construction.
+pub struct Generated {
+    pub input: String,
+    pub answers: Option<(Answer, Answer)>,
+}
+
+/// Location ID pairs, with some IDs repeated between the lists so part 2 has something to count.
+pub fn day1(rng: &mut Rng, pairs: usize) -> Generated {
+    let left: Vec<u64> = (0..pairs).map(|_| rng.range(10_000, 99_999)).collect();
+    let right: Vec<u64> = (0..pairs)
+        .map(|_| {
+            if rng.chance(30) {
+                *rng.choose(&left)
+            } else {
+                rng.range(10_000, 99_999)
+            }
+        })
+        .collect();
+    let input = left
+        .iter()
+        .zip(&right)
+        .map(|(left, right)| format!("{left}   {right}\n"))
+        .collect();
+
+    let (mut sorted_left, mut sorted_right) = (left.clone(), right.clone());
+    sorted_left.sort();
+    sorted_right.sort();
+    let part1: u64 = sorted_left
+        .iter()
+        .zip(&sorted_right)
+        .map(|(left, right)| left.abs_diff(*right))
+        .sum();
+    let part2: u64 = left
+        .iter()
+        .map(|left| left * right.iter().filter(|right| *right == left).count() as u64)
+        .sum();
+    Generated {
+        input,
+        answers: Some((part1.into(), part2.into())),
+    }
+}
+
+fn safe_report(rng: &mut Rng, levels: usize) -> Vec<u64> {
+    let mut report = vec![0];
+    for _ in 1..levels {
+        report.push(report.last().unwrap() + rng.range(1, 3));
+    }
+    // shift away from zero, and flip half of them so they decrease instead.
+    let offset = rng.range(1, 10);
+    let peak = offset + report.last().unwrap();
+    let decreasing = rng.chance(50);
+    report
+        .into_iter()
+        .map(|level| {
+            if decreasing {
+                peak - level
+            } else {
+                level + offset
+            }
+        })
+        .collect()
+}
+
+/// Reports that are a mix of safe ones, ones made safe by removing a single duplicated
+/// level, and ones with two duplicated levels too far apart for the dampener to fix.
+/// Reports are between 5 and `max_levels` levels long, which is capped at 30 so levels
+/// fit in the solver's `i8`s.
+pub fn day2(rng: &mut Rng, reports: usize, max_levels: usize) -> Generated {
+    let max_levels = max_levels.clamp(5, 30) as u64;
+    let (mut safe, mut dampened) = (0_u64, 0_u64);
+    let mut input = String::new();
+    for _ in 0..reports {
+        let levels = rng.range(5, max_levels) as usize;
+        let report = match rng.range(0, 9) {
+            0..=3 => {
+                safe += 1;
+                safe_report(rng, levels)
+            }
+            4..=6 => {
+                dampened += 1;
+                let mut report = safe_report(rng, levels - 1);
+                let duplicate = rng.range(0, levels as u64 - 2) as usize;
+                report.insert(duplicate, report[duplicate]);
+                report
+            }
+            _ => {
+                let mut report = safe_report(rng, levels - 2);
+                let first = rng.range(0, levels as u64 - 5) as usize;
+                let second = rng.range(first as u64 + 2, levels as u64 - 3) as usize;
+                report.insert(second, report[second]);
+                report.insert(first, report[first]);
+                report
+            }
+        };
+        let levels: Vec<String> = report.iter().map(|level| level.to_string()).collect();
+        input += &(levels.join(" ") + "\n");
+    }
+    Generated {
+        input,
+        answers: Some((safe.into(), (safe + dampened).into())),
+    }
+}
+
+// Fragments that look a bit like instructions but aren't, none of which can combine with
+// a neighbour to form a real one.
+const DAY3_NOISE: [&str; 16] = [
+    "mul[3,7]",
+    "mul(32,64]",
+    "mul ( 2 , 4 )",
+    "mul(4*",
+    "do_not_",
+    "don't",
+    "undo",
+    "select(",
+    "then(",
+    "from()",
+    "what()",
+    "%&",
+    "!@^",
+    "+",
+    "?",
+    "[",
+];
+
+/// Corrupted memory holding `instructions` real `mul`, `do` and `don't` instructions,
+/// mixed in with noise and the odd line break.
+pub fn day3(rng: &mut Rng, instructions: usize) -> Generated {
+    let mut input = String::new();
+    let (mut part1, mut part2) = (0_u64, 0_u64);
+    let mut enabled = true;
+    for _ in 0..instructions {
+        while rng.chance(40) {
+            input += *rng.choose(&DAY3_NOISE);
+        }
+        match rng.range(0, 9) {
+            0 => {
+                enabled = true;
+                input += "do()";
+            }
+            1 => {
+                enabled = false;
+                input += "don't()";
+            }
+            _ => {
+                let (left, right) = (rng.range(1, 999), rng.range(1, 999));
+                part1 += left * right;
+                if enabled {
+                    part2 += left * right;
+                }
+                input += &format!("mul({left},{right})");
+            }
+        }
+        if rng.chance(1) {
+            input += "\n";
+        }
+    }
+    input += "\n";
+    Generated {
+        input,
+        answers: Some((part1.into(), part2.into())),
+    }
+}
+
+/// A word search of random `X`, `M`, `A` and `S` letters, with the answers found by trying
+/// every direction from every cell.
+pub fn day4(rng: &mut Rng, rows: usize, columns: usize) -> Generated {
+    let grid: Vec<Vec<u8>> = (0..rows)
+        .map(|_| (0..columns).map(|_| *rng.choose(b"XMAS")).collect())
+        .collect();
+    let input = grid
+        .iter()
+        .map(|row| String::from_utf8_lossy(row).into_owned() + "\n")
+        .collect();
+
+    // whether `word` can be read from `(row, column)` taking steps of `(down, right)`.
+    let reads = |word: &[u8], (row, column): (isize, isize), (down, right): (isize, isize)| {
+        word.iter().zip(0..).all(|(letter, step)| {
+            let (row, column) = (row + down * step, column + right * step);
+            row >= 0
+                && column >= 0
+                && grid
+                    .get(row as usize)
+                    .and_then(|cells| cells.get(column as usize))
+                    == Some(letter)
+        })
+    };
+    let directions = [
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (0, -1),
+        (0, 1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+    ];
+    let (mut part1, mut part2) = (0_u64, 0_u64);
+    for row in 0..rows as isize {
+        for column in 0..columns as isize {
+            for direction in directions {
+                part1 += u64::from(reads(b"XMAS", (row, column), direction));
+            }
+            let diagonal = |start, direction| {
+                reads(b"MAS", start, direction) || reads(b"SAM", start, direction)
+            };
+            part2 += u64::from(
+                diagonal((row - 1, column - 1), (1, 1)) && diagonal((row - 1, column + 1), (1, -1)),
+            );
+        }
+    }
+    Generated {
+        input,
+        answers: Some((part1.into(), part2.into())),
+    }
+}
+
+/// Page ordering rules between every pair of `pages` distinct pages, so there are
+/// `pages * (pages - 1) / 2` of them, followed by `updates` updates of odd length up to
+/// `max_update_length`, about half of which are out of order.
+pub fn day5(rng: &mut Rng, pages: usize, updates: usize, max_update_length: usize) -> Generated {
+    let mut order: Vec<u64> = (10..100).collect();
+    rng.shuffle(&mut order);
+    order.truncate(pages.clamp(3, 90));
+
+    let mut rules = Vec::new();
+    for (index, before) in order.iter().enumerate() {
+        for after in &order[index + 1..] {
+            rules.push(format!("{before}|{after}\n"));
+        }
+    }
+    rng.shuffle(&mut rules);
+    let mut input = rules.concat() + "\n";
+
+    let max_update_length = max_update_length.clamp(1, order.len()) as u64;
+    let (mut part1, mut part2) = (0_u64, 0_u64);
+    for _ in 0..updates {
+        let length = rng.range(0, (max_update_length - 1) / 2) as usize * 2 + 1;
+        let mut sorted = order.clone();
+        rng.shuffle(&mut sorted);
+        sorted.truncate(length);
+        sorted.sort_by_key(|page| order.iter().position(|other| other == page));
+        let mut update = sorted.clone();
+        if rng.chance(50) {
+            rng.shuffle(&mut update);
+        }
+        if update == sorted {
+            part1 += sorted[length / 2];
+        } else {
+            part2 += sorted[length / 2];
+        }
+        let update: Vec<String> = update.iter().map(|page| page.to_string()).collect();
+        input += &(update.join(",") + "\n");
+    }
+    Generated {
+        input,
+        answers: Some((part1.into(), part2.into())),
+    }
+}
+
+/// A map with roughly `density` percent of cells blocked, and a guard facing up on one
+/// of the free ones, with the answers found by walking the guard round the map.
+pub fn day6(rng: &mut Rng, rows: usize, columns: usize, density: u64) -> Generated {
+    let mut cells: Vec<Vec<char>> = (0..rows.max(1))
+        .map(|_| {
+            (0..columns.max(1))
+                .map(|_| if rng.chance(density) { '#' } else { '.' })
+                .collect()
+        })
+        .collect();
+    let (row, column) = (
+        rng.range(0, rows.max(1) as u64 - 1) as usize,
+        rng.range(0, columns.max(1) as u64 - 1) as usize,
+    );
+    cells[row][column] = '^';
+
+    // a random map can trap the guard, which the puzzle's never does, so the new obstacle
+    // is only tried on the positions the guard visits either way.
+    let (visited, _) = day6_walk(&cells, (row, column), None);
+    let part2 = visited
+        .iter()
+        .filter(|&&position| position != (row, column))
+        .filter(|&&position| day6_walk(&cells, (row, column), Some(position)).1)
+        .count();
+    Generated {
+        input: cells
+            .iter()
+            .map(|row| row.iter().collect::<String>() + "\n")
+            .collect(),
+        answers: Some((visited.len().into(), part2.into())),
+    }
+}
+
+// Walks a guard from `start` facing up and turning right in front of obstacles, including
+// `extra` if there is one, until they leave the map or get back to a state they've already
+// been in. Returns the positions they visited and whether they were going round in circles.
+fn day6_walk(
+    cells: &[Vec<char>],
+    start: (usize, usize),
+    extra: Option<(usize, usize)>,
+) -> (HashSet<(usize, usize)>, bool) {
+    const STEPS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+    let mut states = HashSet::new();
+    let (mut position, mut direction) = (start, 0);
+    while states.insert((position, direction)) {
+        let (down, right) = STEPS[direction];
+        let next = (
+            position.0.wrapping_add_signed(down),
+            position.1.wrapping_add_signed(right),
+        );
+        match cells.get(next.0).and_then(|row| row.get(next.1)) {
+            None => return (positions(&states), false),
+            Some('#') => direction = (direction + 1) % 4,
+            Some(_) if Some(next) == extra => direction = (direction + 1) % 4,
+            Some(_) => position = next,
+        }
+    }
+    (positions(&states), true)
+}
+
+fn positions(states: &HashSet<((usize, usize), usize)>) -> HashSet<(usize, usize)> {
+    states.iter().map(|(position, _)| *position).collect()
+}
+
+// Keeps totals in the same ballpark as a real input, so adding them all up can't overflow.
+const DAY7_MAX_RESULT: u64 = 100_000_000_000_000;
+
+fn concat(a: u64, b: u64) -> Option<u64> {
+    a.checked_mul(10_u64.checked_pow(b.ilog10() + 1)?)?
+        .checked_add(b)
+}
+
+/// Equations with between 2 and `max_terms` terms, each of which is either satisfiable
+/// with `+` and `*`, or only once `||` is allowed, or not at all.
+///
+/// Every operator only ever makes the running total bigger, and `||` more so than the
+/// other two, so joining all the terms with `||` gives a total that nothing else can reach.
+/// That means that total needs `||`, and one more than it can't be made at all.
+pub fn day7(rng: &mut Rng, equations: usize, max_terms: usize) -> Generated {
+    let max_terms = max_terms.clamp(2, 12) as u64;
+    let (mut part1, mut part2) = (0_u64, 0_u64);
+    let mut input = String::new();
+    for _ in 0..equations {
+        let mut terms: Vec<u64> = (0..rng.range(2, max_terms))
+            .map(|_| rng.range(1, 99))
+            .collect();
+        let kind = rng.range(0, 4);
+        // drop terms until the result is small enough.
+        let result = loop {
+            let result = match kind {
+                0 | 1 => terms[1..].iter().try_fold(terms[0], |total, term| {
+                    if rng.chance(50) {
+                        total.checked_add(*term)
+                    } else {
+                        total.checked_mul(*term)
+                    }
+                }),
+                2 => terms[1..]
+                    .iter()
+                    .try_fold(terms[0], |total, term| concat(total, *term)),
+                _ => terms[1..]
+                    .iter()
+                    .try_fold(terms[0], |total, term| concat(total, *term))
+                    .and_then(|total| total.checked_add(1)),
+            };
+            match result {
+                Some(result) if result <= DAY7_MAX_RESULT => break result,
+                _ => {
+                    terms.pop();
+                }
+            }
+        };
+        match kind {
+            0 | 1 => {
+                part1 += result;
+                part2 += result;
+            }
+            2 => part2 += result,
+            _ => {}
+        }
+        let terms: Vec<String> = terms.iter().map(|term| term.to_string()).collect();
+        input += &format!("{result}: {}\n", terms.join(" "));
+    }
+    Generated {
+        input,
+        answers: Some((part1.into(), part2.into())),
+    }
+}
+
+/// Generates an input for `day` from named size parameters, falling back to roughly the
+/// size of a real puzzle input for any that aren't given.
+pub fn generate(
+    day: u32,
+    seed: u64,
+    parameters: &HashMap<String, usize>,
+) -> Result<Generated, String> {
+    let known: &[(&str, usize)] = match day {
+        1 => &[("pairs", 1000)],
+        2 => &[("reports", 1000), ("max_levels", 8)],
+        3 => &[("instructions", 800)],
+        4 => &[("rows", 140), ("columns", 140)],
+        5 => &[("pages", 49), ("updates", 200), ("max_update_length", 23)],
+        6 => &[("rows", 130), ("columns", 130), ("density", 2)],
+        7 => &[("equations", 850), ("max_terms", 12)],
+        _ => return Err(format!("there's no generator for day {day}")),
+    };
+    if let Some(unknown) = parameters
+        .keys()
+        .find(|name| !known.iter().any(|(known, _)| known == name))
+    {
+        let names: Vec<&str> = known.iter().map(|(name, _)| *name).collect();
+        return Err(format!(
+            "day {day} doesn't take '{unknown}', it takes {}",
+            names.join(", ")
+        ));
+    }
+    let get = |name: &str| {
+        parameters.get(name).copied().unwrap_or_else(|| {
+            known
+                .iter()
+                .find(|(known, _)| *known == name)
+                .map(|(_, default)| *default)
+                .unwrap()
+        })
+    };
+
+    let mut rng = Rng::new(seed);
+    Ok(match day {
+        1 => day1(&mut rng, get("pairs")),
+        2 => day2(&mut rng, get("reports"), get("max_levels")),
+        3 => day3(&mut rng, get("instructions")),
+        4 => day4(&mut rng, get("rows"), get("columns")),
+        5 => day5(
+            &mut rng,
+            get("pages"),
+            get("updates"),
+            get("max_update_length"),
+        ),
+        6 => day6(&mut rng, get("rows"), get("columns"), get("density") as u64),
+        _ => day7(&mut rng, get("equations"), get("max_terms")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{day1, day2, day3, day4, day5, day6, day7};
+
+    fn generate_default(day: u32, seed: u64) -> Generated {
+        generate(day, seed, &HashMap::new()).unwrap()
+    }
+
+    #[test]
+    fn test_same_seed_same_input() {
+        for day in 1..=7 {
+            assert_eq!(
+                generate_default(day, 7).input,
+                generate_default(day, 7).input
+            );
+            assert_ne!(
+                generate_default(day, 7).input,
+                generate_default(day, 8).input
+            );
+        }
+    }
+
+    #[test]
+    fn test_known_answers() {
+        for seed in 0..5 {
+            let answers = |day| generate_default(day, seed).answers.unwrap();
+            let input = generate_default(1, seed).input;
+            assert_eq!(
+                (
+                    day1::part1(&input).unwrap().into(),
+                    day1::part2(&input).unwrap().into()
+                ),
+                answers(1)
+            );
+            let input = day2::input_generator(&generate_default(2, seed).input).unwrap();
+            assert_eq!(
+                (day2::part1(&input).into(), day2::part2(&input).into()),
+                answers(2)
+            );
+            let input = day3::input_generator(&generate_default(3, seed).input).unwrap();
+            assert_eq!(
+                (
+                    day3::part1(&input).unwrap().into(),
+                    day3::part2(&input).unwrap().into()
+                ),
+                answers(3)
+            );
+            let input = day4::input_generator(&generate_default(4, seed).input).unwrap();
+            assert_eq!(
+                (day4::part1(&input).into(), day4::part2(&input).into()),
+                answers(4)
+            );
+            let input = generate_default(5, seed).input;
+            assert_eq!(
+                (
+                    day5::part1(&input).unwrap().into(),
+                    day5::part2(&input).unwrap().into()
+                ),
+                answers(5)
+            );
+            let input = day6::input_generator(&generate_default(6, seed).input).unwrap();
+            assert_eq!(
+                (day6::part1(&input).into(), day6::part2(&input).into()),
+                answers(6)
+            );
+            let input = day7::input_generator(&generate_default(7, seed).input).unwrap();
+            assert_eq!(
+                (
+                    day7::part1(&input).unwrap().into(),
+                    day7::part2(&input).unwrap().into()
+                ),
+                answers(7)
+            );
+        }
+    }
+
+    #[test]
+    fn test_known_answers_for_grids() {
+        let parameters = HashMap::from([("rows".to_owned(), 20), ("columns".to_owned(), 30)]);
+        for seed in 0..20 {
+            let generated = generate(4, seed, &parameters).unwrap();
+            let input = day4::input_generator(&generated.input).unwrap();
+            assert_eq!(
+                Some((day4::part1(&input).into(), day4::part2(&input).into())),
+                generated.answers
+            );
+            let generated = generate(6, seed, &parameters).unwrap();
+            let input = day6::input_generator(&generated.input).unwrap();
+            assert_eq!(
+                Some((day6::part1(&input).into(), day6::part2(&input).into())),
+                generated.answers
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_parameter() {
+        let parameters = HashMap::from([("rows".to_owned(), 20)]);
+        assert!(generate(1, 0, &parameters).is_err());
+        assert!(generate(8, 0, &HashMap::new()).is_err());
+    }
+}
@@ -1,5 +1,10 @@
 use aoc_runner_derive::aoc_lib;
 
+#[cfg(feature = "count-allocations")]
+pub mod allocations;
+pub mod answer;
+pub mod bytes;
+pub mod conformance;
 pub mod day1;
 pub mod day2;
 pub mod day3;
@@ -7,5 +12,29 @@ pub mod day4;
 pub mod day5;
 pub mod day6;
 pub mod day7;
+pub mod determinism;
+#[cfg(feature = "embedded-inputs")]
+pub mod embedded;
+pub mod error;
+pub mod fetch;
+pub mod generate;
+pub mod parallelism;
+pub mod progress;
+pub mod scaffold;
+pub mod solution;
+pub mod trace;
+pub mod watch;
+
+pub use answer::Answer;
+pub use error::ParseError;
+pub use solution::Solution;
+
+/// Reads `AOC_PARALLELISM` and `AOC_CONFORMANCE` and applies them, so that a binary can
+/// refuse to start with a bad value instead of the solvers warning and using the defaults.
+pub fn configure_from_env() -> Result<(), String> {
+    parallelism::configure(parallelism::Config::from_env()?);
+    conformance::configure(conformance::Conformance::from_env()?);
+    Ok(())
+}
 
 aoc_lib! { year = 2024 }
@@ -1,3 +1,59 @@
-use aoc_runner_derive::aoc_main;
+use std::process::ExitCode;
 
-aoc_main! { lib = aoc_2024 }
+#[cfg(not(feature = "embedded-inputs"))]
+fn main() -> ExitCode {
+    if let Err(error) = aoc_2024::configure_from_env() {
+        eprintln!("{error}");
+        return ExitCode::FAILURE;
+    }
+    // aoc-runner's main is declared in here so that it can run after the configuration.
+    aoc_runner_derive::aoc_main! { lib = aoc_2024 }
+    main();
+    ExitCode::SUCCESS
+}
+
+// Runs every implementation of every day on the embedded inputs, printing the same way as
+// aoc-runner does.
+#[cfg(feature = "embedded-inputs")]
+fn main() -> ExitCode {
+    use aoc_2024::embedded;
+    use aoc_2024::solution::{self, DEFAULT};
+    use std::time::Instant;
+
+    if let Err(error) = aoc_2024::configure_from_env() {
+        eprintln!("{error}");
+        return ExitCode::FAILURE;
+    }
+    let mut result = ExitCode::SUCCESS;
+    for solution in solution::SOLUTIONS {
+        let day = solution.day();
+        let Some(input) = embedded::input(day) else {
+            println!("Day {day}: no input was embedded, skipping\n");
+            continue;
+        };
+        let start = Instant::now();
+        let parsed = match solution.parse(input) {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                eprintln!("Day {day}: {error}\n");
+                result = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        let generator = start.elapsed();
+        for part in [1, 2] {
+            for name in solution.implementations(part) {
+                let start = Instant::now();
+                let answer = solution.run(part, name, &*parsed).unwrap();
+                let runner = start.elapsed();
+                let answer = solution::describe(&answer);
+                match name {
+                    DEFAULT => println!("Day {day} - Part {part}: {answer}"),
+                    _ => println!("Day {day} - Part {part} - {name}: {answer}"),
+                }
+                println!("\tgenerator: {generator:?},\n\trunner: {runner:?}\n");
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,257 @@
+//! Chooses whether each solver runs sequentially or on a rayon thread pool, and how many
+//! threads it gets. The configuration comes from the `AOC_PARALLELISM` environment variable,
+//! which is read the first time a solver runs unless [`configure`] was called before then.
+//! It's a comma separated list of settings that apply to every solver, a whole day or a single part, with the most
+//! specific one winning:
+//!
+//! ```text
+//! AOC_PARALLELISM=sequential
+//! AOC_PARALLELISM=4,day6=parallel,day7.part2=sequential
+//! ```
+//!
+//! A setting is `sequential`, `parallel` (rayon's global pool) or a number of threads.
+//! Solvers without a setting keep their usual mode.
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+pub const ENVIRONMENT_VARIABLE: &str = "AOC_PARALLELISM";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    Sequential,
+    /// Runs on rayon's global pool when there's no thread count.
+    Parallel {
+        threads: Option<usize>,
+    },
+}
+
+impl Mode {
+    pub const PARALLEL: Mode = Mode::Parallel { threads: None };
+}
+
+// In the same format as the settings in `AOC_PARALLELISM`.
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sequential => write!(f, "sequential"),
+            Self::Parallel { threads: None } => write!(f, "parallel"),
+            Self::Parallel {
+                threads: Some(threads),
+            } => write!(f, "{threads}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Config {
+    all: Option<Mode>,
+    days: HashMap<u32, Mode>,
+    parts: HashMap<(u32, u32), Mode>,
+}
+
+impl Config {
+    /// A configuration that runs every solver in `mode`.
+    pub fn all(mode: Mode) -> Self {
+        Self {
+            all: Some(mode),
+            ..Self::default()
+        }
+    }
+
+    /// Parses a configuration in the format of `AOC_PARALLELISM`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        for entry in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            match entry.split_once('=') {
+                Some((solver, mode)) => {
+                    let mode = parse_mode(mode.trim())?;
+                    match parse_solver(solver.trim())? {
+                        (day, Some(part)) => config.parts.insert((day, part), mode),
+                        (day, None) => config.days.insert(day, mode),
+                    };
+                }
+                None => config.all = Some(parse_mode(entry)?),
+            }
+        }
+        Ok(config)
+    }
+
+    /// The configuration from `AOC_PARALLELISM`, which is empty when it isn't set.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var(ENVIRONMENT_VARIABLE) {
+            Ok(spec) => {
+                Self::parse(&spec).map_err(|error| format!("{ENVIRONMENT_VARIABLE}: {error}"))
+            }
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// The mode for the given part, falling back to `default` if nothing applies to it.
+    pub fn mode(&self, day: u32, part: u32, default: Mode) -> Mode {
+        self.parts
+            .get(&(day, part))
+            .or_else(|| self.days.get(&day))
+            .or(self.all.as_ref())
+            .copied()
+            .unwrap_or(default)
+    }
+}
+
+fn parse_mode(mode: &str) -> Result<Mode, String> {
+    match mode {
+        "sequential" => Ok(Mode::Sequential),
+        "parallel" => Ok(Mode::PARALLEL),
+        _ => match mode.parse() {
+            Ok(0) | Err(_) => Err(format!(
+                "'{mode}' should be sequential, parallel or a number of threads"
+            )),
+            Ok(threads) => Ok(Mode::Parallel {
+                threads: Some(threads),
+            }),
+        },
+    }
+}
+
+fn parse_solver(solver: &str) -> Result<(u32, Option<u32>), String> {
+    let invalid = || format!("'{solver}' should be a day like day6 or a part like day6.part2");
+    let (day, part) = match solver.split_once('.') {
+        Some((day, part)) => (day, Some(part)),
+        None => (solver, None),
+    };
+    let day = day
+        .strip_prefix("day")
+        .and_then(|day| day.parse().ok())
+        .ok_or_else(invalid)?;
+    let part = match part {
+        Some(part) => Some(
+            part.strip_prefix("part")
+                .and_then(|part| part.parse().ok())
+                .ok_or_else(invalid)?,
+        ),
+        None => None,
+    };
+    Ok((day, part))
+}
+
+thread_local! {
+    static OVERRIDE: RefCell<Option<Config>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `config` in place of the one given to [`configure`] for any solvers it
+/// calls on this thread.
+pub fn with_config<R>(config: Config, f: impl FnOnce() -> R) -> R {
+    let previous = OVERRIDE.replace(Some(config));
+    let result = f();
+    OVERRIDE.set(previous);
+    result
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Sets the configuration for every solver not run inside [`with_config`], in place of the
+/// one from `AOC_PARALLELISM`. It has no effect once a solver has run.
+pub fn configure(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+// A bad `AOC_PARALLELISM` shouldn't stop a solver halfway through, so it's reported once and
+// every solver keeps its usual mode.
+fn config() -> &'static Config {
+    CONFIG.get_or_init(|| {
+        Config::from_env().unwrap_or_else(|error| {
+            eprintln!("{error}, so it's being ignored");
+            Config::default()
+        })
+    })
+}
+
+fn thread_pool(threads: usize) -> Arc<ThreadPool> {
+    static POOLS: OnceLock<Mutex<HashMap<usize, Arc<ThreadPool>>>> = OnceLock::new();
+    let mut pools = POOLS.get_or_init(Default::default).lock().unwrap();
+    pools
+        .entry(threads)
+        .or_insert_with(|| {
+            Arc::new(
+                ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .expect("failed to start a thread pool"),
+            )
+        })
+        .clone()
+}
+
+/// Runs a part in the configured mode for it. `solve` is told whether to use rayon, and
+/// when it does everything it spawns runs on the configured pool.
+pub fn run<R: Send>(day: u32, part: u32, default: Mode, solve: impl FnOnce(bool) -> R + Send) -> R {
+    let mode = OVERRIDE.with_borrow(|overridden| match overridden {
+        Some(overridden) => overridden.mode(day, part, default),
+        None => config().mode(day, part, default),
+    });
+    match mode {
+        Mode::Sequential => solve(false),
+        Mode::Parallel { threads: None } => solve(true),
+        Mode::Parallel {
+            threads: Some(threads),
+        } => thread_pool(threads).install(|| solve(true)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse("4, day6=parallel,day7.part2=sequential").unwrap();
+        assert_eq!(
+            config.mode(1, 1, Mode::Sequential),
+            Mode::Parallel { threads: Some(4) }
+        );
+        assert_eq!(config.mode(6, 2, Mode::Sequential), Mode::PARALLEL);
+        assert_eq!(
+            config.mode(7, 1, Mode::Sequential),
+            Mode::Parallel { threads: Some(4) }
+        );
+        assert_eq!(config.mode(7, 2, Mode::PARALLEL), Mode::Sequential);
+        assert_eq!(
+            Config::parse("").unwrap().mode(1, 1, Mode::PARALLEL),
+            Mode::PARALLEL
+        );
+        assert!(Config::parse("0").is_err());
+        assert!(Config::parse("day6.2=sequential").is_err());
+        assert!(Config::parse("day6=fast").is_err());
+        for mode in [
+            Mode::Sequential,
+            Mode::PARALLEL,
+            Mode::Parallel { threads: Some(3) },
+        ] {
+            assert_eq!(Config::parse(&mode.to_string()), Ok(Config::all(mode)));
+        }
+    }
+
+    #[test]
+    fn test_run_with_config() {
+        let threads = |config| {
+            with_config(config, || {
+                run(1, 1, Mode::Sequential, |parallel| {
+                    parallel.then(rayon::current_num_threads)
+                })
+            })
+        };
+        assert_eq!(threads(Config::default()), None);
+        assert_eq!(threads(Config::parse("day1=3").unwrap()), Some(3));
+        assert_eq!(
+            threads(Config::parse("day1.part1=sequential").unwrap()),
+            None
+        );
+    }
+}
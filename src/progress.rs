@@ -0,0 +1,246 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const START_MARKER: &str = "<!-- progress:start -->";
+pub const END_MARKER: &str = "<!-- progress:end -->";
+
+const DONE: &str = ":heavy_check_mark:";
+const NOT_DONE: &str = ":x:";
+const FAILING: &str = ":warning:";
+
+/// Finds the `(day, part)` pairs that have an `#[aoc(dayN, partM)]` solution somewhere
+/// in the `dayN.rs` files under `source_directory`, leaving out any that are still stubs.
+pub fn discover_parts(source_directory: &Path) -> io::Result<BTreeSet<(u32, u32)>> {
+    let mut parts = BTreeSet::new();
+    for entry in fs::read_dir(source_directory)? {
+        let path = entry?.path();
+        let is_day = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("day") && name.ends_with(".rs"));
+        if is_day {
+            parts.extend(parse_parts(&fs::read_to_string(path)?));
+        }
+    }
+    Ok(parts)
+}
+
+fn parse_parts(source: &str) -> impl Iterator<Item = (u32, u32)> + '_ {
+    let lines: Vec<&str> = source.lines().collect();
+    (0..lines.len()).filter_map(move |index| {
+        let attribute = lines[index].trim().strip_prefix("#[aoc(day")?;
+        let (day, rest) = attribute.split_once(',')?;
+        let part = rest.trim().strip_prefix("part")?;
+        let part = part.split(|c: char| !c.is_ascii_digit()).next()?;
+        if function_body(&lines[index + 1..]).is_some_and(is_stub) {
+            return None;
+        }
+        Some((day.parse().ok()?, part.parse().ok()?))
+    })
+}
+
+// The lines between the braces of the function at the start of `lines`, assuming it's
+// laid out the way rustfmt would with the closing brace level with the `fn`.
+fn function_body<'lines, 'source>(lines: &'lines [&'source str]) -> Option<&'lines [&'source str]> {
+    let signature = lines
+        .iter()
+        .position(|line| !line.trim().starts_with("#["))?;
+    let line = lines[signature];
+    let end = format!("{}}}", &line[..line.len() - line.trim_start().len()]);
+    let open = lines[signature..]
+        .iter()
+        .position(|line| line.trim_end().ends_with('{'))?;
+    let body = &lines[signature + open + 1..];
+    let close = body.iter().position(|line| line.trim_end() == end)?;
+    Some(&body[..close])
+}
+
+// Parts that only panic or return a placeholder haven't been solved yet, whether or not
+// they've been marked with `#[aoc]`.
+fn is_stub(body: &[&str]) -> bool {
+    let code: Vec<&str> = body
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+        .collect();
+    match code[..] {
+        [] => true,
+        [line] => {
+            ["0", "Default::default()"].contains(&line)
+                || ["todo!(", "unimplemented!("]
+                    .iter()
+                    .any(|stub| line.starts_with(stub))
+        }
+        _ => false,
+    }
+}
+
+/// Picks the puzzle titles back out of an existing progress table so they survive regeneration.
+pub fn parse_titles(readme: &str) -> HashMap<u32, String> {
+    readme
+        .lines()
+        .filter_map(|line| {
+            let name = line.strip_prefix("|[Day ")?.split_once(']')?.0;
+            let (day, title) = name.split_once(": ")?;
+            Some((day.parse().ok()?, title.to_owned()))
+        })
+        .collect()
+}
+
+/// Reads which of the `test_day_N_part_M` tests passed from the output of `cargo test`.
+pub fn parse_test_results(output: &str) -> HashMap<(u32, u32), bool> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (_, test) = line.strip_prefix("test ")?.split_once("::test_day_")?;
+            let (name, result) = test.split_once(" ... ")?;
+            let (day, part) = name.split_once("_part_")?;
+            Some(((day.parse().ok()?, part.parse().ok()?), result == "ok"))
+        })
+        .collect()
+}
+
+/// Reads the runner time of each unnamed part from the output of the `aoc-2024` binary.
+pub fn parse_timings(output: &str) -> HashMap<(u32, u32), String> {
+    let mut timings = HashMap::new();
+    let mut current = None;
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("Day ") {
+            current = rest.split_once(" - Part ").and_then(|(day, rest)| {
+                let (part, _) = rest.split_once(": ")?;
+                Some((day.parse().ok()?, part.parse().ok()?))
+            });
+        } else if let Some(runner) = line.trim().strip_prefix("runner: ") {
+            if let Some(day_part) = current.take() {
+                timings.insert(day_part, runner.to_owned());
+            }
+        }
+    }
+    timings
+}
+
+pub struct Progress {
+    pub implemented: BTreeSet<(u32, u32)>,
+    pub titles: HashMap<u32, String>,
+    pub test_results: Option<HashMap<(u32, u32), bool>>,
+    pub timings: HashMap<(u32, u32), String>,
+}
+
+impl Progress {
+    fn cell(&self, day: u32, part: u32) -> String {
+        let status = if !self.implemented.contains(&(day, part)) {
+            NOT_DONE
+        } else {
+            match &self.test_results {
+                Some(results) if !results.get(&(day, part)).copied().unwrap_or(false) => FAILING,
+                _ => DONE,
+            }
+        };
+        match self.timings.get(&(day, part)) {
+            Some(timing) => format!("{status} ({timing})"),
+            None => status.to_owned(),
+        }
+    }
+
+    pub fn render_table(&self) -> String {
+        let mut table = String::from("|Puzzle|Part 1|Part 2|\n|:-:|:-:|:-:|\n");
+        for day in 1..=25 {
+            let puzzle = match self.titles.get(&day) {
+                Some(title) => {
+                    format!("[Day {day}: {title}](https://adventofcode.com/2024/day/{day})")
+                }
+                None => format!("Day {day}"),
+            };
+            table += &format!("|{puzzle}|{}|{}|\n", self.cell(day, 1), self.cell(day, 2));
+        }
+        table
+    }
+}
+
+/// Swaps whatever is between the progress markers for `table`, or returns `None`
+/// if the README doesn't have the markers.
+pub fn replace_table(readme: &str, table: &str) -> Option<String> {
+    let (before, rest) = readme.split_once(START_MARKER)?;
+    let (_, after) = rest.split_once(END_MARKER)?;
+    Some(format!(
+        "{before}{START_MARKER}\n{table}{END_MARKER}{after}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_parts() {
+        const SOURCE: &str = "#[aoc(day6, part1)]
+pub fn part1(input: &Map) -> usize {
+    get_distinct_positions(input).len()
+}
+
+#[aoc(day6, part2, Jump)]
+pub fn part2_jump(
+    input: &Map,
+) -> usize {
+    // not done yet
+    todo!()
+}
+
+#[aoc(day6, part2)]
+pub fn part2(input: &Map) -> usize {
+    loop_positions(input).len()
+}
+
+    #[aoc(day8, part1)]
+    fn part1(_input: &[String]) -> u32 {
+        0
+    }
+";
+        assert_eq!(parse_parts(SOURCE).collect::<Vec<_>>(), [(6, 1), (6, 2)]);
+    }
+
+    #[test]
+    fn test_parse_outputs() {
+        const TEST_OUTPUT: &str = "test day1::tests::test_day_1_part_1 ... ok
+test day1::tests::test_day_1_part_2 ... FAILED
+test answer::tests::test_display ... ok";
+        const RUN_OUTPUT: &str = "Advent of code 2024
+Day 1 - Part 1: 11
+\tgenerator: 1.2µs,
+\trunner: 25.5µs
+
+Day 1 - Part 1 - Fast: 11
+\tgenerator: 1.2µs,
+\trunner: 1µs
+";
+        let results = parse_test_results(TEST_OUTPUT);
+        assert_eq!(results.len(), 2);
+        assert!(results[&(1, 1)]);
+        assert!(!results[&(1, 2)]);
+        let timings = parse_timings(RUN_OUTPUT);
+        assert_eq!(timings.len(), 1);
+        assert_eq!(timings[&(1, 1)], "25.5µs");
+    }
+
+    #[test]
+    fn test_render_and_replace() {
+        const README: &str = "# Progress
+<!-- progress:start -->
+|[Day 1: Historian Hysteria](https://adventofcode.com/2024/day/1)|:x:|:x:|
+<!-- progress:end -->
+footer
+";
+        let progress = Progress {
+            implemented: [(1, 1), (1, 2)].into(),
+            titles: parse_titles(README),
+            test_results: Some([((1, 1), true)].into()),
+            timings: [((1, 1), "25.5µs".to_owned())].into(),
+        };
+        let readme = replace_table(README, &progress.render_table()).unwrap();
+        assert!(readme.contains("|[Day 1: Historian Hysteria](https://adventofcode.com/2024/day/1)|:heavy_check_mark: (25.5µs)|:warning:|\n|Day 2|:x:|:x:|\n"));
+        assert!(readme.ends_with("|Day 25|:x:|:x:|\n<!-- progress:end -->\nfooter\n"));
+        assert_eq!(replace_table("# Progress\n", ""), None);
+    }
+}
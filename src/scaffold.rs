@@ -0,0 +1,284 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// The parts return a placeholder until they're solved, which `aoc-tools readme` leaves
+// unticked.
+const TEMPLATE: &str = "use crate::bytes;
+use crate::error::{Overflow, ParseError};
+use crate::solution::Solution;
+use crate::Answer;
+use aoc_runner_derive::aoc;
+use aoc_runner_derive::aoc_generator;
+
+type IntegerType = u32;
+
+#[aoc_generator(day__DAY__)]
+pub fn input_generator(input: &str) -> Result<Vec<Vec<u8>>, ParseError> {
+    Ok(bytes::lines(input.as_bytes()).map(<[u8]>::to_vec).collect())
+}
+
+#[aoc(day__DAY__, part1)]
+pub fn part1(_input: &[Vec<u8>]) -> IntegerType {
+    0
+}
+
+#[aoc(day__DAY__, part2)]
+pub fn part2(_input: &[Vec<u8>]) -> IntegerType {
+    0
+}
+
+pub struct Day__DAY__;
+
+impl Solution for Day__DAY__ {
+    const DAY: u32 = __DAY__;
+    type Input = Vec<Vec<u8>>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        input_generator(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, Overflow> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, Overflow> {
+        Ok(part2(input).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = \"
+    \";
+
+    #[test]
+    fn test_day___DAY___part_1() {
+        const EXPECTED: IntegerType = 0;
+        let output = part1(&input_generator(INPUT).unwrap());
+        assert_eq!(output, EXPECTED);
+    }
+
+    #[test]
+    fn test_day___DAY___part_2() {
+        const EXPECTED: IntegerType = 0;
+        let output = part2(&input_generator(INPUT).unwrap());
+        assert_eq!(output, EXPECTED);
+    }
+}
+";
+
+/// Sets up everything needed to start on a new day under `root`: the source file from a
+/// template, its `pub mod` in `lib.rs`, its entry in the solution registry, an empty input
+/// file and a link in the README's progress table. Anything that already exists is left
+/// alone, and the returned list describes only what was actually changed.
+pub fn new_day(root: &Path, day: u32, title: Option<&str>) -> io::Result<Vec<String>> {
+    let mut changes = Vec::new();
+
+    let source_path = root.join(format!("src/day{day}.rs"));
+    if !source_path.exists() {
+        fs::write(&source_path, TEMPLATE.replace("__DAY__", &day.to_string()))?;
+        changes.push(format!("created {}", source_path.display()));
+    }
+
+    let lib_path = root.join("src/lib.rs");
+    let lib = fs::read_to_string(&lib_path)?;
+    if let Some(lib) = register_module(&lib, day) {
+        fs::write(&lib_path, lib)?;
+        changes.push(format!("registered day{day} in {}", lib_path.display()));
+    }
+
+    let registry_path = root.join("src/solution.rs");
+    let registry = fs::read_to_string(&registry_path)?;
+    if let Some(registry) = register_solution(&registry, day) {
+        fs::write(&registry_path, registry)?;
+        changes.push(format!(
+            "registered Day{day} in {}",
+            registry_path.display()
+        ));
+    }
+
+    // aoc-runner includes every day's input in the binary, so there has to be something
+    // there until the real one is fetched over it.
+    let input_path = root.join(format!("input/2024/day{day}.txt"));
+    if !input_path.exists() {
+        fs::create_dir_all(input_path.parent().unwrap())?;
+        fs::write(&input_path, "")?;
+        changes.push(format!("created {}", input_path.display()));
+    }
+
+    let readme_path = root.join("README.md");
+    let readme = fs::read_to_string(&readme_path)?;
+    if let Some(readme) = link_progress_row(&readme, day, title) {
+        fs::write(&readme_path, readme)?;
+        changes.push(format!("linked day {day} in {}", readme_path.display()));
+    }
+
+    Ok(changes)
+}
+
+fn module_day(line: &str) -> Option<u32> {
+    line.trim()
+        .strip_prefix("pub mod day")?
+        .strip_suffix(';')?
+        .parse()
+        .ok()
+}
+
+fn registry_day(line: &str) -> Option<u32> {
+    line.trim()
+        .strip_prefix("&Registered::<crate::day")?
+        .split_once("::")?
+        .0
+        .parse()
+        .ok()
+}
+
+// Keeps lines for each day in numeric order by inserting before the first later day,
+// or after the last earlier one.
+fn insert_in_day_order(
+    text: &str,
+    day: u32,
+    line_day: impl Fn(&str) -> Option<u32>,
+    new_line: &str,
+) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.iter().any(|line| line_day(line) == Some(day)) {
+        return None;
+    }
+    let position = lines
+        .iter()
+        .position(|line| line_day(line).is_some_and(|other| other > day))
+        .or_else(|| {
+            lines
+                .iter()
+                .rposition(|line| line_day(line).is_some())
+                .map(|index| index + 1)
+        })?;
+    let mut lines = lines;
+    lines.insert(position, new_line);
+    Some(lines.join("\n") + "\n")
+}
+
+fn register_module(lib: &str, day: u32) -> Option<String> {
+    insert_in_day_order(lib, day, module_day, &format!("pub mod day{day};"))
+}
+
+fn register_solution(registry: &str, day: u32) -> Option<String> {
+    let entry = format!("    &Registered::<crate::day{day}::Day{day}>::new(),");
+    insert_in_day_order(registry, day, registry_day, &entry)
+}
+
+fn link_progress_row(readme: &str, day: u32, title: Option<&str>) -> Option<String> {
+    let unlinked = format!("|Day {day}|");
+    let mut changed = false;
+    let lines: Vec<String> = readme
+        .lines()
+        .map(|line| match line.strip_prefix(&unlinked) {
+            Some(rest) => {
+                changed = true;
+                let name = match title {
+                    Some(title) => format!("Day {day}: {title}"),
+                    None => format!("Day {day}"),
+                };
+                format!("|[{name}](https://adventofcode.com/2024/day/{day})|{rest}")
+            }
+            None => line.to_owned(),
+        })
+        .collect();
+    changed.then(|| lines.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress;
+    use std::env;
+
+    const LIB: &str = "use aoc_runner_derive::aoc_lib;
+
+pub mod answer;
+pub mod day1;
+pub mod day2;
+pub mod day10;
+pub mod fetch;
+
+aoc_lib! { year = 2024 }
+";
+
+    const README: &str = "# Progress
+|Puzzle|Part 1|Part 2|
+|:-:|:-:|:-:|
+|[Day 1: Historian Hysteria](https://adventofcode.com/2024/day/1)|:heavy_check_mark:|:heavy_check_mark:|
+|Day 8|:x:|:x:|
+|Day 9|:x:|:x:|
+";
+
+    #[test]
+    fn test_register_module() {
+        let lib = register_module(LIB, 8).unwrap();
+        assert!(lib.contains("pub mod day2;\npub mod day8;\npub mod day10;\n"));
+        assert_eq!(register_module(&lib, 8), None);
+        let lib = register_module(&lib, 11).unwrap();
+        assert!(lib.contains("pub mod day10;\npub mod day11;\npub mod fetch;\n"));
+    }
+
+    #[test]
+    fn test_register_solution() {
+        const REGISTRY: &str = "pub static SOLUTIONS: &[&dyn DynSolution] = &[
+    &Registered::<crate::day1::Day1>::new(),
+    &Registered::<crate::day7::Day7>::new(),
+];
+";
+        let registry = register_solution(REGISTRY, 8).unwrap();
+        assert!(registry.contains(
+            "crate::day7::Day7>::new(),\n    &Registered::<crate::day8::Day8>::new(),\n];"
+        ));
+        assert_eq!(register_solution(&registry, 8), None);
+    }
+
+    #[test]
+    fn test_link_progress_row() {
+        let readme = link_progress_row(README, 8, Some("Resonant Collinearity")).unwrap();
+        assert!(readme.contains(
+            "|[Day 8: Resonant Collinearity](https://adventofcode.com/2024/day/8)|:x:|:x:|\n|Day 9|"
+        ));
+        assert_eq!(link_progress_row(&readme, 8, None), None);
+        assert_eq!(link_progress_row(README, 1, None), None);
+    }
+
+    #[test]
+    fn test_new_day_is_idempotent() {
+        let root = env::temp_dir().join(format!("aoc-2024-scaffold-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), LIB).unwrap();
+        fs::write(
+            root.join("src/solution.rs"),
+            "&[\n    &Registered::<crate::day1::Day1>::new(),\n];\n",
+        )
+        .unwrap();
+        fs::write(root.join("README.md"), README).unwrap();
+
+        assert_eq!(new_day(&root, 9, None).unwrap().len(), 5);
+        let source = fs::read_to_string(root.join("src/day9.rs")).unwrap();
+        assert!(source.contains("#[aoc(day9, part1)]"));
+        assert!(source.contains("fn test_day_9_part_1()"));
+        assert!(source.contains("impl Solution for Day9 {\n    const DAY: u32 = 9;"));
+        assert!(fs::read_to_string(root.join("src/solution.rs"))
+            .unwrap()
+            .contains("crate::day9::Day9"));
+        assert_eq!(
+            fs::read_to_string(root.join("input/2024/day9.txt")).unwrap(),
+            ""
+        );
+        assert!(progress::discover_parts(&root.join("src"))
+            .unwrap()
+            .is_empty());
+        assert!(new_day(&root, 9, None).unwrap().is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
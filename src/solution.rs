@@ -0,0 +1,271 @@
+//! A way to call each day's solution from code, alongside the `#[aoc]` attributes that
+//! cargo-aoc and the `aoc-2024` binary use. Parts can have alternative implementations,
+//! which [`cross_check`] runs against the main ones to make sure they all agree.
+
+use crate::error::{Overflow, ParseError};
+use crate::Answer;
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+/// The name of each part's main implementation, which is the one aoc-runner leaves unnamed.
+pub const DEFAULT: &str = "default";
+
+/// A part's answer, or [`Overflow`] if it's too large to work out.
+pub type PartResult = Result<Answer, Overflow>;
+
+/// Another way of solving a part, which should always give the same answer as the main one.
+pub struct Alternative<I> {
+    pub part: u32,
+    /// Matches the name in the part's `#[aoc(dayN, partM, Name)]` attribute.
+    pub name: &'static str,
+    pub solve: fn(&I) -> PartResult,
+}
+
+/// One day's puzzle. The parts return a [`PartResult`] rather than a bare [`Answer`], since
+/// on inputs far larger than the puzzle's an answer can be too large for the type a part
+/// works it out in, and that's reported as an [`Overflow`] instead of a wrong answer or a
+/// panic. It's the only way a part can fail once its input has been parsed.
+pub trait Solution {
+    const DAY: u32;
+    type Input: 'static;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError>;
+    fn part1(input: &Self::Input) -> PartResult;
+    fn part2(input: &Self::Input) -> PartResult;
+
+    const ALTERNATIVES: &'static [Alternative<Self::Input>] = &[];
+}
+
+/// A [`Solution`] with its input type hidden, so that solutions for different days can be
+/// kept together in [`SOLUTIONS`].
+pub trait DynSolution: Sync {
+    fn day(&self) -> u32;
+    fn parse(&self, input: &str) -> Result<Box<dyn Any>, ParseError>;
+    /// Panics if `input` didn't come from this solution's [`DynSolution::parse`].
+    fn part1(&self, input: &dyn Any) -> PartResult;
+    /// Panics if `input` didn't come from this solution's [`DynSolution::parse`].
+    fn part2(&self, input: &dyn Any) -> PartResult;
+    /// The names of every implementation of `part`, starting with [`DEFAULT`].
+    fn implementations(&self, part: u32) -> Vec<&'static str>;
+    /// Runs the named implementation of `part`, or returns `None` if there isn't one.
+    fn run(&self, part: u32, name: &str, input: &dyn Any) -> Option<PartResult>;
+
+    fn solve(&self, input: &str) -> Result<(Answer, Answer), ParseError> {
+        let input = self.parse(input)?;
+        Ok((self.part1(&*input)?, self.part2(&*input)?))
+    }
+}
+
+// `fn() -> S` keeps this `Sync` whatever `S` is, since it's only ever a marker type.
+pub struct Registered<S>(PhantomData<fn() -> S>);
+
+impl<S> Registered<S> {
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<S> Default for Registered<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Solution> Registered<S> {
+    fn input<'input>(&self, input: &'input dyn Any) -> &'input S::Input {
+        input
+            .downcast_ref()
+            .unwrap_or_else(|| panic!("input wasn't parsed by day {}", S::DAY))
+    }
+}
+
+impl<S: Solution> DynSolution for Registered<S> {
+    fn day(&self) -> u32 {
+        S::DAY
+    }
+
+    fn parse(&self, input: &str) -> Result<Box<dyn Any>, ParseError> {
+        Ok(Box::new(S::parse(input)?))
+    }
+
+    fn part1(&self, input: &dyn Any) -> PartResult {
+        S::part1(self.input(input))
+    }
+
+    fn part2(&self, input: &dyn Any) -> PartResult {
+        S::part2(self.input(input))
+    }
+
+    fn implementations(&self, part: u32) -> Vec<&'static str> {
+        let alternatives = S::ALTERNATIVES
+            .iter()
+            .filter(|alternative| alternative.part == part)
+            .map(|alternative| alternative.name);
+        std::iter::once(DEFAULT).chain(alternatives).collect()
+    }
+
+    fn run(&self, part: u32, name: &str, input: &dyn Any) -> Option<PartResult> {
+        match (part, name) {
+            (1, DEFAULT) => Some(self.part1(input)),
+            (2, DEFAULT) => Some(self.part2(input)),
+            _ => S::ALTERNATIVES
+                .iter()
+                .find(|alternative| alternative.part == part && alternative.name == name)
+                .map(|alternative| (alternative.solve)(self.input(input))),
+        }
+    }
+}
+
+/// Every day's solution, in order.
+pub static SOLUTIONS: &[&dyn DynSolution] = &[
+    &Registered::<crate::day1::Day1>::new(),
+    &Registered::<crate::day2::Day2>::new(),
+    &Registered::<crate::day3::Day3>::new(),
+    &Registered::<crate::day4::Day4>::new(),
+    &Registered::<crate::day5::Day5>::new(),
+    &Registered::<crate::day6::Day6>::new(),
+    &Registered::<crate::day7::Day7>::new(),
+];
+
+/// The solution for the given day, if there is one yet.
+pub fn get(day: u32) -> Option<&'static dyn DynSolution> {
+    SOLUTIONS
+        .iter()
+        .copied()
+        .find(|solution| solution.day() == day)
+}
+
+/// The answer a part gave, or why it failed, for printing alongside other answers.
+pub fn describe(result: &PartResult) -> String {
+    match result {
+        Ok(answer) => answer.to_string(),
+        Err(error) => format!("failed: {error}"),
+    }
+}
+
+pub struct Timing {
+    pub name: &'static str,
+    pub answer: PartResult,
+    pub elapsed: Duration,
+}
+
+/// The answer from every implementation of one part, in the order they ran.
+pub struct CrossCheck {
+    pub day: u32,
+    pub part: u32,
+    pub timings: Vec<Timing>,
+}
+
+impl CrossCheck {
+    pub fn agrees(&self) -> bool {
+        self.timings
+            .windows(2)
+            .all(|pair| pair[0].answer == pair[1].answer)
+    }
+}
+
+impl fmt::Display for CrossCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Day {} - Part {}", self.day, self.part)?;
+        if !self.agrees() {
+            write!(f, " (implementations disagree)")?;
+        }
+        for timing in &self.timings {
+            write!(
+                f,
+                "\n\t{}: {} ({:?})",
+                timing.name,
+                describe(&timing.answer),
+                timing.elapsed
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `input` once and runs every implementation of both parts on it, timing each one.
+pub fn cross_check(solution: &dyn DynSolution, input: &str) -> Result<Vec<CrossCheck>, ParseError> {
+    let input = solution.parse(input)?;
+    Ok([1, 2]
+        .into_iter()
+        .map(|part| CrossCheck {
+            day: solution.day(),
+            part,
+            timings: solution
+                .implementations(part)
+                .into_iter()
+                .map(|name| {
+                    let start = Instant::now();
+                    let answer = solution.run(part, name, &*input).unwrap();
+                    Timing {
+                        name,
+                        answer,
+                        elapsed: start.elapsed(),
+                    }
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let days: Vec<u32> = SOLUTIONS.iter().map(|solution| solution.day()).collect();
+        assert_eq!(days, (1..=7).collect::<Vec<_>>());
+        assert!(get(25).is_none());
+
+        let day7 = get(7).unwrap();
+        let input = "190: 10 19\n3267: 81 40 27\n292: 11 6 16 20\n192: 17 8 14";
+        assert_eq!(
+            day7.solve(input),
+            Ok((Answer::from(3749_u64), Answer::from(3941_u64)))
+        );
+        assert!(matches!(
+            get(1).unwrap().solve("3 4\n4"),
+            Err(ParseError::Malformed { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_cross_check() {
+        let day7 = get(7).unwrap();
+        assert_eq!(day7.implementations(2), [DEFAULT, "Reverse"]);
+        assert_eq!(get(1).unwrap().implementations(1), [DEFAULT]);
+        assert_eq!(day7.run(1, "Forward", &()), None);
+
+        let checks = cross_check(day7, "190: 10 19\n3267: 81 40 27\n7290: 6 8 6 15").unwrap();
+        assert_eq!(checks.len(), 2);
+        assert!(checks.iter().all(CrossCheck::agrees));
+        assert_eq!(checks[1].timings[1].answer, Ok(Answer::from(10747_u64)));
+
+        let disagreeing = CrossCheck {
+            day: 7,
+            part: 1,
+            timings: vec![
+                Timing {
+                    name: DEFAULT,
+                    answer: Ok(Answer::from(1)),
+                    elapsed: Duration::ZERO,
+                },
+                Timing {
+                    name: "Reverse",
+                    answer: Err(Overflow),
+                    elapsed: Duration::ZERO,
+                },
+            ],
+        };
+        assert!(!disagreeing.agrees());
+        assert!(disagreeing
+            .to_string()
+            .starts_with("Day 7 - Part 1 (implementations disagree)\n\tdefault: 1 ("));
+        assert!(disagreeing
+            .to_string()
+            .contains("\n\tReverse: failed: the answer is too large for this solution ("));
+    }
+}
@@ -0,0 +1,66 @@
+//! Spans around every generator and part, and events for the interesting things that
+//! happen in them, built in with the `tracing` feature. Without it the macros here expand
+//! to nothing, so the solvers don't pay anything for them.
+
+/// Enters an info span until the end of the enclosing block, taking the same arguments as
+/// `tracing::info_span!`.
+macro_rules! span {
+    ($($arguments:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($($arguments)*).entered();
+    };
+}
+
+/// Records an event at a level named like the `tracing` macros, e.g.
+/// `event!(debug, row, column, "loop detected")`.
+macro_rules! event {
+    ($level:ident, $($arguments:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arguments)*);
+    };
+}
+
+pub(crate) use {event, span};
+
+/// Counts something to report in an event, like the branches of a search. It's empty
+/// without the `tracing` feature, so counting costs nothing.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Counter {
+    #[cfg(feature = "tracing")]
+    count: usize,
+}
+
+impl Counter {
+    #[inline(always)]
+    pub(crate) fn increment(&mut self) {
+        #[cfg(feature = "tracing")]
+        {
+            self.count += 1;
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Logs to stderr with more detail for each level of `verbosity`: spans with their timings
+/// at 1, events from the solvers at 2 and everything at 3 or more. Nothing is logged at 0.
+#[cfg(feature = "tracing")]
+pub fn init(verbosity: u8) {
+    use tracing::Level;
+    use tracing_subscriber::fmt::format::FmtSpan;
+
+    let level = match verbosity {
+        0 => return,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .init();
+}
@@ -0,0 +1,225 @@
+//! Keeps track of a day's test results and answers between runs of `aoc-tools watch`, so
+//! that each run can show what changed since the one before.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Answer {
+    pub value: String,
+    pub elapsed: String,
+}
+
+/// Everything worth comparing from one run of a day's tests and implementations.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Run {
+    /// Whether each test passed, by name.
+    pub tests: BTreeMap<String, bool>,
+    /// The answer from each implementation by part and name, in the order they ran.
+    pub answers: Vec<((u32, String), Answer)>,
+}
+
+/// Reads whether each of a day's tests passed from the output of `cargo test`.
+pub fn parse_tests(output: &str, day: u32) -> BTreeMap<String, bool> {
+    let prefix = format!("test day{day}::tests::");
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, result) = line.strip_prefix(&prefix)?.split_once(" ... ")?;
+            Some((name.to_owned(), result == "ok"))
+        })
+        .collect()
+}
+
+/// Reads each implementation's answer and time from the output of `aoc-tools check`.
+/// Grid answers span several lines, which are kept together with a `|` between rows.
+pub fn parse_answers(output: &str) -> Vec<((u32, String), Answer)> {
+    let mut answers = Vec::new();
+    let mut part = None;
+    let mut last = None;
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("Day ") {
+            part = rest
+                .split_once(" - Part ")
+                .and_then(|(_, rest)| rest.split(' ').next()?.parse().ok());
+            last = None;
+        } else if let Some(rest) = line.strip_prefix('\t') {
+            let Some(part) = part else { continue };
+            if let Some((name, answer)) = rest.split_once(": ") {
+                let (value, elapsed) = answer
+                    .rsplit_once(" (")
+                    .map_or((answer, ""), |(value, elapsed)| {
+                        (value, elapsed.trim_end_matches(')'))
+                    });
+                answers.push((
+                    (part, name.to_owned()),
+                    Answer {
+                        value: value.to_owned(),
+                        elapsed: elapsed.to_owned(),
+                    },
+                ));
+                last = Some(answers.len() - 1);
+            }
+        } else if let Some((_, answer)) = last.and_then(|index| answers.get_mut(index)) {
+            if !line.is_empty() {
+                let (row, elapsed) = line
+                    .rsplit_once(" (")
+                    .map_or((line, None), |(row, elapsed)| {
+                        (row, Some(elapsed.trim_end_matches(')')))
+                    });
+                if !answer.value.is_empty() {
+                    answer.value.push('|');
+                }
+                answer.value.push_str(row);
+                if let Some(elapsed) = elapsed {
+                    answer.elapsed = elapsed.to_owned();
+                }
+            }
+        }
+    }
+    answers
+}
+
+impl Run {
+    fn answer(&self, part: u32, name: &str) -> Option<&Answer> {
+        self.answers
+            .iter()
+            .find(|((answer_part, answer_name), _)| *answer_part == part && answer_name == name)
+            .map(|(_, answer)| answer)
+    }
+
+    /// Describes this run, marking anything that's different from `previous`.
+    pub fn report(&self, previous: Option<&Run>) -> String {
+        let mut report = String::new();
+        let failed: Vec<&str> = self
+            .tests
+            .iter()
+            .filter(|(_, passed)| !**passed)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        write!(
+            report,
+            "tests: {} passed, {} failed",
+            self.tests.len() - failed.len(),
+            failed.len()
+        )
+        .unwrap();
+        if !failed.is_empty() {
+            write!(report, " ({})", failed.join(", ")).unwrap();
+        }
+        report.push('\n');
+        if let Some(previous) = previous {
+            for (name, passed) in &self.tests {
+                match previous.tests.get(name) {
+                    Some(before) if before != passed => {
+                        let now = if *passed { "passing" } else { "failing" };
+                        writeln!(report, "  {name} is now {now}").unwrap();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for ((part, name), answer) in &self.answers {
+            write!(report, "part {part} {name}: ").unwrap();
+            match previous.map(|previous| previous.answer(*part, name)) {
+                Some(Some(before)) if before.value != answer.value => write!(
+                    report,
+                    "{} -> {} (changed), {} -> {}",
+                    before.value, answer.value, before.elapsed, answer.elapsed
+                ),
+                Some(Some(before)) => write!(
+                    report,
+                    "{}, {} -> {}",
+                    answer.value, before.elapsed, answer.elapsed
+                ),
+                Some(None) => write!(report, "{} (new), {}", answer.value, answer.elapsed),
+                None => write!(report, "{}, {}", answer.value, answer.elapsed),
+            }
+            .unwrap();
+            report.push('\n');
+        }
+        if let Some(previous) = previous {
+            for ((part, name), _) in &previous.answers {
+                if self.answer(*part, name).is_none() {
+                    writeln!(report, "part {part} {name}: gone").unwrap();
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_OUTPUT: &str = "
+running 3 tests
+test day6::tests::test_day_6_part_1 ... ok
+test day6::tests::test_day_6_part_2 ... FAILED
+test day7::tests::test_day_7_part_1 ... ok
+";
+
+    const CHECK_OUTPUT: &str = "Day 6 - Part 1
+\tdefault: 41 (12.5µs)
+
+Day 6 - Part 2 (implementations disagree)
+\tdefault: 6 (1.2ms)
+\tJump: 5 (80µs)
+\tGrid: \n#.\n.# (3µs)
+";
+
+    #[test]
+    fn test_parse() {
+        let tests = parse_tests(TEST_OUTPUT, 6);
+        assert_eq!(tests.len(), 2);
+        assert!(!tests["test_day_6_part_2"]);
+
+        let answers = parse_answers(CHECK_OUTPUT);
+        let names: Vec<_> = answers
+            .iter()
+            .map(|((part, name), _)| (*part, name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [(1, "default"), (2, "default"), (2, "Jump"), (2, "Grid")]
+        );
+        assert_eq!(
+            answers[0].1,
+            Answer {
+                value: "41".to_owned(),
+                elapsed: "12.5µs".to_owned()
+            }
+        );
+        assert_eq!(answers[2].1.value, "5");
+        assert_eq!(answers[3].1.value, "#.|.#");
+        assert_eq!(answers[3].1.elapsed, "3µs");
+    }
+
+    #[test]
+    fn test_report() {
+        let before = Run {
+            tests: parse_tests(TEST_OUTPUT, 6),
+            answers: parse_answers(CHECK_OUTPUT),
+        };
+        let mut after = before.clone();
+        after.tests.insert("test_day_6_part_2".to_owned(), true);
+        after.answers[2].1 = Answer {
+            value: "6".to_owned(),
+            elapsed: "75µs".to_owned(),
+        };
+        after.answers.pop();
+
+        assert!(before.report(None).starts_with(
+            "tests: 1 passed, 1 failed (test_day_6_part_2)\npart 1 default: 41, 12.5µs\n"
+        ));
+        let report = after.report(Some(&before));
+        assert!(
+            report.starts_with("tests: 2 passed, 0 failed\n  test_day_6_part_2 is now passing\n")
+        );
+        assert!(report.contains("part 1 default: 41, 12.5µs -> 12.5µs\n"));
+        assert!(report.contains("part 2 Jump: 5 -> 6 (changed), 80µs -> 75µs\n"));
+        assert!(report.ends_with("part 2 Grid: gone\n"));
+    }
+}
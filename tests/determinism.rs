@@ -0,0 +1,59 @@
+use aoc_2024::determinism::{self, Options};
+use aoc_2024::generate;
+use aoc_2024::solution;
+use std::collections::HashMap;
+
+// Runs a day's example alongside a few small generated inputs, so that any state one of
+// them leaves behind shows up in the answers for the others.
+fn assert_repeatable(day: u32, example: &str, parameters: &[(&str, usize)]) {
+    let parameters: HashMap<String, usize> = parameters
+        .iter()
+        .map(|(name, value)| (name.to_string(), *value))
+        .collect();
+    let mut inputs = vec![example.to_owned()];
+    for seed in 1..=3 {
+        inputs.push(generate::generate(day, seed, &parameters).unwrap().input);
+    }
+    let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+    let options = Options {
+        rounds: 4,
+        ..Options::default()
+    };
+
+    let results = determinism::check(solution::get(day).unwrap(), &inputs, &options).unwrap();
+    assert!(!results.is_empty());
+    for result in results {
+        assert!(result.is_deterministic(), "{result}");
+        let runs = &result.answers[0].1;
+        assert_eq!(runs.len(), options.rounds * options.modes.len());
+    }
+}
+
+#[test]
+fn test_day_6_is_repeatable() {
+    const EXAMPLE: &str = "....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...";
+    assert_repeatable(6, EXAMPLE, &[("rows", 24), ("columns", 24)]);
+}
+
+#[test]
+fn test_day_7_is_repeatable() {
+    const EXAMPLE: &str = "190: 10 19
+3267: 81 40 27
+83: 17 5
+156: 15 6
+7290: 6 8 6 15
+161011: 16 10 13
+192: 17 8 14
+21037: 9 7 18 13
+292: 11 6 16 20";
+    assert_repeatable(7, EXAMPLE, &[("equations", 40), ("max_terms", 8)]);
+}